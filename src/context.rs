@@ -31,6 +31,7 @@ impl<'r> FromRequest<'r> for Context {
             {"path": "/blog", "name": "Blog"},
             {"path": "/fava", "name": "Fava", "subpaths": [
                 {"path": "/fava/ingest", "name": "Ingest"},
+                {"path": "/fava/ingest/rules", "name": "Rules"},
                 {"path": "/fava/beancount", "name": "Beancount"},
                 {"path": "/fava/graphs", "name": "Graphs"},
             ]},
@@ -0,0 +1,127 @@
+use rocket::form::Form;
+use rocket::serde::json::serde_json::json;
+use rocket::{response::Redirect, routes, Build, Rocket, State};
+use rocket_dyn_templates::Template;
+
+use crate::fava::ScanConfigConfig;
+use crate::repository::Repository;
+use crate::util::Error;
+use crate::{context::Context, oauth::AuthUser};
+
+use super::ingest::FavaAccounts;
+use super::models::iban;
+use super::models::own_accounts::{self, OwnAccount, OwnAccounts};
+use super::models::{transfers, Scans};
+
+fn render(
+    mut context: Context,
+    own: &[OwnAccount],
+    scans: &Scans,
+    accounts: &FavaAccounts,
+    errors: &[Error],
+) -> Template {
+    // Accounts of ours that pending scans have statements of, but that are not mapped yet
+    let unmapped: Vec<_> = scans.with(|scans| {
+        let mut unmapped: Vec<(String, usize)> = Vec::new();
+        for (number, count) in scans.iter().flat_map(|x| x.own_accounts()) {
+            if own_accounts::lookup(own, &number).is_some() {
+                continue;
+            }
+            match unmapped.iter_mut().find(|(x, _)| *x == number) {
+                Some((_, total)) => *total += count,
+                None => unmapped.push((number, count)),
+            }
+        }
+        unmapped
+    });
+    let unmapped: Vec<_> = unmapped
+        .into_iter()
+        .map(|(number, count)| json!({ "iban": iban::format(&number), "count": count }))
+        .collect();
+    let own: Vec<_> = own
+        .iter()
+        .map(|x| json!({ "id": x.id, "iban": iban::format(&x.iban), "account": x.account }))
+        .collect();
+
+    context.merge(json!({
+        "own": own,
+        "unmapped": unmapped,
+        "pay_options": accounts.pay_options,
+        "errors": errors,
+    }));
+    Template::render("fava/ingest/accounts", context.value())
+}
+
+#[get("/")]
+fn get(
+    context: Context,
+    own: &State<OwnAccounts>,
+    scans: &State<Scans>,
+    accounts: &State<FavaAccounts>,
+    user: AuthUser,
+) -> Result<Template, Redirect> {
+    user.check()?;
+    // Scans are always locked before our accounts, render needs both
+    let own = own.with(|own| own.clone());
+    Ok(render(context, &own, scans, accounts, &[]))
+}
+
+#[derive(FromForm)]
+struct OwnAccountForm<'r> {
+    iban: &'r str,
+    account: &'r str,
+}
+
+#[post("/", data = "<user_input>")]
+fn post(
+    context: Context,
+    user_input: Form<OwnAccountForm<'_>>,
+    own: &State<OwnAccounts>,
+    scans: &State<Scans>,
+    accounts: &State<FavaAccounts>,
+    user: AuthUser,
+) -> Result<Redirect, Template> {
+    if let Err(e) = user.check() {
+        return Ok(e);
+    }
+
+    let mapping = match OwnAccount::new(user_input.iban, user_input.account.trim()) {
+        Ok(x) => x,
+        Err(e) => {
+            let own = own.with(|own| own.clone());
+            let error = Error::new("Invalid mapping", &e);
+            return Err(render(context, &own, scans, accounts, &[error]));
+        }
+    };
+
+    let own = own.with_save(|own| {
+        // A bank account maps to one asset account, the newest mapping wins
+        own.retain(|x| x.iban != mapping.iban);
+        own.push(mapping);
+        own.clone()
+    });
+    // Pending statements of the newly mapped account can be halves of transfers
+    scans.with_save(|scans| transfers::merge_transfers(scans, &own));
+
+    Ok(Redirect::to("/fava/ingest/accounts"))
+}
+
+#[delete("/<mapping_id>")]
+fn delete(mapping_id: &str, own: &State<OwnAccounts>, user: AuthUser) -> Redirect {
+    if let Err(e) = user.check() {
+        return e;
+    }
+
+    own.with_save(|own| own.retain(|x| x.id != mapping_id));
+    Redirect::to("/fava/ingest/accounts")
+}
+
+pub fn fuel(rocket: Rocket<Build>) -> Rocket<Build> {
+    rocket
+        .mount("/fava/ingest/accounts", routes![get, post, delete])
+        .attach(Repository::<Vec<OwnAccount>>::adhoc(
+            "accounts config",
+            |c: &ScanConfigConfig| c.accounts_location.to_string(),
+            vec![],
+        ))
+}
@@ -0,0 +1,95 @@
+use rocket::form::Form;
+use rocket::serde::json::serde_json::json;
+use rocket::{response::Redirect, routes, Build, Rocket, State};
+use rocket_dyn_templates::Template;
+
+use crate::fava::ScanConfigConfig;
+use crate::repository::Repository;
+use crate::util::Error;
+use crate::{context::Context, oauth::AuthUser};
+
+use super::models::aliases::{self, Alias, Aliases};
+use super::models::ledger;
+
+fn render(mut context: Context, aliases: &[Alias], errors: &[Error]) -> Template {
+    context.merge(json!({
+        "aliases": aliases,
+        "errors": errors,
+    }));
+    Template::render("fava/ingest/aliases", context.value())
+}
+
+#[get("/")]
+fn get(context: Context, aliases: &State<Aliases>, user: AuthUser) -> Result<Template, Redirect> {
+    user.check()?;
+    Ok(aliases.with(|aliases| render(context, aliases, &[])))
+}
+
+#[derive(FromForm)]
+struct AliasForm<'r> {
+    pattern: &'r str,
+    payee: &'r str,
+}
+
+#[post("/", data = "<user_input>")]
+fn post(
+    context: Context,
+    user_input: Form<AliasForm<'_>>,
+    aliases: &State<Aliases>,
+    user: AuthUser,
+) -> Result<Redirect, Template> {
+    if let Err(e) = user.check() {
+        return Ok(e);
+    }
+
+    let payee = user_input.payee.trim();
+    let alias = if payee.is_empty() {
+        Err(Error::new("Invalid alias", "An alias needs a payee."))
+    } else {
+        Alias::new(user_input.pattern, payee)
+            .map_err(|e| Error::new("Invalid regex", &e.to_string()))
+    };
+
+    aliases.with_save(|aliases| match alias {
+        Ok(alias) => {
+            aliases.push(alias);
+            Ok(Redirect::to("/fava/ingest/aliases"))
+        }
+        Err(e) => Err(render(context, aliases, &[e])),
+    })
+}
+
+#[delete("/<alias_id>")]
+fn delete(alias_id: &str, aliases: &State<Aliases>, user: AuthUser) -> Redirect {
+    if let Err(e) = user.check() {
+        return e;
+    }
+
+    aliases.with_save(|aliases| aliases.retain(|x| x.id != alias_id));
+    Redirect::to("/fava/ingest/aliases")
+}
+
+/// Adds an alias for every payee of the ledger that has none yet
+#[post("/seed")]
+fn seed(aliases: &State<Aliases>, config: &State<ScanConfigConfig>, user: AuthUser) -> Redirect {
+    if let Err(e) = user.check() {
+        return e;
+    }
+
+    let ledger = ledger::read(&config.beancount_location);
+    aliases.with_save(|aliases| {
+        let seeded = aliases::seed(aliases, &ledger);
+        aliases.extend(seeded);
+    });
+    Redirect::to("/fava/ingest/aliases")
+}
+
+pub fn fuel(rocket: Rocket<Build>) -> Rocket<Build> {
+    rocket
+        .mount("/fava/ingest/aliases", routes![get, post, delete, seed])
+        .attach(Repository::<Vec<Alias>>::adhoc(
+            "aliases config",
+            |c: &ScanConfigConfig| c.aliases_location.to_string(),
+            vec![],
+        ))
+}
@@ -0,0 +1,320 @@
+use std::fs;
+
+use crate::fava::ScanConfigConfig;
+use crate::repository::Repository;
+
+use super::ingest::{append_scan, push_scan};
+use super::lock::Lock;
+use super::models::aliases::Aliases;
+use super::models::importer;
+use super::models::invoices::Invoices;
+use super::models::own_accounts::OwnAccounts;
+use super::models::rules::Rules;
+use super::models::{Imports, Scans};
+
+const USAGE: &str = "\
+Usage: only_scan [command]
+
+Without a command the web server starts.
+
+Commands:
+    import <file> [--bank <bank>]    Reads a bank export into a new scan
+    list-scans                       Shows the pending scans
+    list-groups <scan>               Shows the groups of a scan and their category
+    apply-rules [<scan>]             Categorises with the rules, every scan when none is given
+    categorise <scan> <group> <account>
+                                     Categorises a group of a scan as <account>
+    list-duplicates <scan>           Shows the suspected duplicates of a scan
+    keep <scan> <statement>          Keeps a suspected duplicate as a genuine statement
+    drop <scan> <statement>          Deletes a suspected duplicate
+    post <scan> --pay <account>      Appends a categorised scan to the ledger, paid from <account>
+    help                             Shows this message
+
+The commands work on the same files as the server, and refuse to run while it is running.
+Otherwise the server would overwrite what they wrote the next time it saves.";
+
+/// The same repositories the server uses, read from the files in the config
+struct Cli {
+    _lock: Lock,
+    config: ScanConfigConfig,
+    scans: Scans,
+    imports: Imports,
+    rules: Rules,
+    aliases: Aliases,
+    invoices: Invoices,
+    own: OwnAccounts,
+}
+
+impl Cli {
+    async fn init() -> Result<Self, String> {
+        let config: ScanConfigConfig = rocket::Config::figment()
+            .extract()
+            .map_err(|e| format!("Invalid config. {}", e))?;
+        let lock = Lock::acquire(&config.lock_location)
+            .map_err(|e| format!("{}\nStop the server before running a command.", e))?;
+
+        Ok(Self {
+            _lock: lock,
+            scans: Repository::init_read(config.ingest_file_location.clone(), vec![]).await,
+            imports: Repository::init_read(config.import_file_location.clone(), vec![]).await,
+            rules: Repository::init_read(config.rules_location.clone(), vec![]).await,
+            aliases: Repository::init_read(config.aliases_location.clone(), vec![]).await,
+            invoices: Repository::init_read(config.invoices_location.clone(), vec![]).await,
+            own: Repository::init_read(config.accounts_location.clone(), vec![]).await,
+            config,
+        })
+    }
+
+    fn import(&self, file: &str, bank: Option<&str>) -> Result<(), String> {
+        let buf = fs::read(file).map_err(|e| format!("{}: {}", file, e))?;
+        let string = String::from_utf8_lossy(&buf);
+        let import = importer::read(&string, bank).map_err(|e| e.to_string())?;
+
+        if import.errors.is_empty() {
+            let count = import.statements.len();
+            let id = push_scan(
+                import.statements,
+                &self.scans,
+                &self.rules,
+                &self.aliases,
+                &self.invoices,
+                &self.own,
+                &self.config,
+            );
+            println!("Scan {} with {} statements", id, count);
+        } else {
+            for error in import.errors.iter() {
+                println!("line {}: {}", error.line, error.error);
+            }
+            println!(
+                "Fix the rows at /fava/ingest/import/{} to make a scan",
+                import.id
+            );
+            self.imports.with_save(|imports| imports.push(import));
+        }
+        Ok(())
+    }
+
+    fn list_scans(&self) {
+        self.scans.with(|scans| {
+            if scans.is_empty() {
+                println!("No pending scans");
+            }
+            for scan in scans.iter() {
+                let (done, total) = scan.count_done();
+                match &scan.source {
+                    Some(source) => println!("{} {}/{} ({})", scan.id, done, total, source),
+                    None => println!("{} {}/{}", scan.id, done, total),
+                }
+            }
+        })
+    }
+
+    fn list_groups(&self, scan_id: &str) -> Result<(), String> {
+        self.scans.with(|scans| {
+            let scan = scans
+                .iter()
+                .find(|x| x.id == scan_id)
+                .ok_or_else(|| format!("No scan {}.", scan_id))?;
+            for group in scan.grouped.iter() {
+                let duplicates = match scan
+                    .duplicates()
+                    .filter(|(g, _)| g.key == group.key)
+                    .count()
+                {
+                    0 => String::new(),
+                    n => format!(", {} suspected duplicates", n),
+                };
+                println!(
+                    "'{}' {} statements, {}{}",
+                    group.key,
+                    group.statements.len(),
+                    group.category.as_deref().unwrap_or("uncategorised"),
+                    duplicates
+                );
+            }
+            Ok(())
+        })
+    }
+
+    fn apply_rules(&self, scan_id: Option<&str>) -> Result<(), String> {
+        self.rules.with(|rules| {
+            self.scans.with_save(|scans| {
+                if let Some(id) = scan_id.filter(|id| !scans.iter().any(|x| &x.id == id)) {
+                    return Err(format!("No scan {}.", id));
+                }
+
+                let picked = scans
+                    .iter_mut()
+                    .filter(|x| scan_id.map(|id| x.id == id).unwrap_or(true));
+                for scan in picked {
+                    let found = scan.edit("Apply the rules", |scan| scan.apply_rules(rules));
+                    let (done, total) = scan.count_done();
+                    println!(
+                        "{} categorised {} groups, {}/{}",
+                        scan.id, found, done, total
+                    );
+                }
+                Ok(())
+            })
+        })
+    }
+
+    /// Group keys often end with a space, they are matched without it
+    fn categorise(&self, scan_id: &str, group: &str, account: &str) -> Result<(), String> {
+        self.scans.with_save(|scans| {
+            let scan = scans
+                .iter_mut()
+                .find(|x| x.id == scan_id)
+                .ok_or_else(|| format!("No scan {}.", scan_id))?;
+            let key = scan
+                .grouped
+                .iter()
+                .map(|x| x.key.clone())
+                .find(|key| key == group || key.trim() == group.trim())
+                .ok_or_else(|| format!("No group '{}' in scan {}.", group, scan_id))?;
+
+            scan.edit(format!("Categorise {} as {}", key, account), |scan| {
+                scan.categorise(&key, account)
+            });
+            let (done, total) = scan.count_done();
+            println!("Categorised '{}' as {}, {}/{}", key, account, done, total);
+            Ok(())
+        })
+    }
+
+    fn list_duplicates(&self, scan_id: &str) -> Result<(), String> {
+        self.scans.with(|scans| {
+            let scan = scans
+                .iter()
+                .find(|x| x.id == scan_id)
+                .ok_or_else(|| format!("No scan {}.", scan_id))?;
+            if scan.duplicates().next().is_none() {
+                println!("No suspected duplicates");
+            }
+            for (group, item) in scan.duplicates() {
+                println!(
+                    "{} {} in '{}': {}",
+                    item.id.0,
+                    item.date.format("%Y-%m-%d"),
+                    group.key,
+                    item.duplicate.as_deref().unwrap_or_default()
+                );
+            }
+            Ok(())
+        })
+    }
+
+    /// Keeps or drops a suspected duplicate, like the duplicates page does
+    fn resolve(&self, scan_id: &str, item_id: &str, keep: bool) -> Result<(), String> {
+        self.scans.with_save(|scans| {
+            let scan = scans
+                .iter_mut()
+                .find(|x| x.id == scan_id)
+                .ok_or_else(|| format!("No scan {}.", scan_id))?;
+            let group = scan
+                .duplicates()
+                .find(|(_, x)| x.id.0 == item_id)
+                .map(|(group, _)| group.key.clone())
+                .ok_or_else(|| {
+                    format!("No suspected duplicate {} in scan {}.", item_id, scan_id)
+                })?;
+
+            if keep {
+                scan.edit("Keep a suspected duplicate", |scan| {
+                    scan.keep(&group, item_id)
+                });
+            } else {
+                scan.edit(format!("Delete a statement of {}", group), |scan| {
+                    scan.delete_item(&group, item_id)
+                });
+            }
+            println!(
+                "{} {}, {} suspected duplicates left",
+                if keep { "Kept" } else { "Dropped" },
+                item_id,
+                scan.duplicates().count()
+            );
+            Ok(())
+        })
+    }
+
+    fn post(&self, scan_id: &str, pay: &str) -> Result<(), String> {
+        self.scans.with_save(|scans| {
+            let index = scans
+                .iter()
+                .position(|x| x.id == scan_id)
+                .ok_or_else(|| format!("No scan {}.", scan_id))?;
+            let scan = &scans[index];
+            if scan.duplicates().next().is_some() {
+                return Err(format!(
+                    "Scan {} has suspected duplicates, keep or drop them first. See list-duplicates.",
+                    scan.id
+                ));
+            }
+            if let Some(group) = scan.get_first() {
+                return Err(format!(
+                    "Scan {} still has uncategorised statements, like group '{}'.",
+                    scan.id, group.key
+                ));
+            }
+
+            append_scan(scan, pay, &self.own, &self.invoices, &self.config).map_err(|e| e.join("\n"))?;
+            println!(
+                "Appended scan {} to {}",
+                scan.id, self.config.beancount_location
+            );
+            scans.remove(index);
+            Ok(())
+        })
+    }
+}
+
+/// Takes `--name value` or `--name=value` out of the arguments
+fn option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, String> {
+    let flag = format!("--{}", name);
+    let prefix = format!("--{}=", name);
+
+    if let Some(index) = args.iter().position(|x| x == &flag) {
+        if index + 1 >= args.len() {
+            return Err(format!("{} needs a value.", flag));
+        }
+        args.remove(index);
+        return Ok(Some(args.remove(index)));
+    }
+    if let Some(index) = args.iter().position(|x| x.starts_with(&prefix)) {
+        return Ok(Some(args.remove(index)[prefix.len()..].to_string()));
+    }
+    Ok(None)
+}
+
+/// Runs `only_scan <command>` without the web server, `args` are the arguments after the binary
+pub async fn run(mut args: Vec<String>) -> Result<(), String> {
+    let bank = option(&mut args, "bank")?;
+    let pay = option(&mut args, "pay")?;
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args.as_slice() {
+        ["help"] | ["--help"] | ["-h"] => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        ["import", file] => Cli::init().await?.import(file, bank.as_deref()),
+        ["list-scans"] => {
+            Cli::init().await?.list_scans();
+            Ok(())
+        }
+        ["list-groups", scan] => Cli::init().await?.list_groups(scan),
+        ["apply-rules"] => Cli::init().await?.apply_rules(None),
+        ["apply-rules", scan] => Cli::init().await?.apply_rules(Some(scan)),
+        ["categorise", scan, group, account] => Cli::init().await?.categorise(scan, group, account),
+        ["list-duplicates", scan] => Cli::init().await?.list_duplicates(scan),
+        ["keep", scan, item] => Cli::init().await?.resolve(scan, item, true),
+        ["drop", scan, item] => Cli::init().await?.resolve(scan, item, false),
+        ["post", scan] => match pay {
+            Some(pay) => Cli::init().await?.post(scan, &pay),
+            None => Err(format!("post needs --pay <account>.\n\n{}", USAGE)),
+        },
+        _ => Err(USAGE.to_string()),
+    }
+}
@@ -4,18 +4,25 @@ use rocket::{fairing::AdHoc, response::Redirect, routes, Build, Rocket, State};
 use rocket_dyn_templates::Template;
 
 use crate::fava::ScanConfigConfig;
+use crate::util::Error;
 use crate::{context::Context, oauth::AuthUser};
 use rocket::serde::json::serde_json::json;
 use rocket::serde::Serialize;
 
 use rocket::data::ToByteUnit;
 use rocket::form::Form;
+use rocket::http::{ContentType, Header};
 use std::collections::HashMap;
 use std::fs;
-use std::io::Cursor;
 
 use crate::repository::Repository;
 
+use super::models::aliases::Aliases;
+use super::models::exporter;
+use super::models::invoices::{self, Invoices};
+use super::models::own_accounts::{self, OwnAccounts};
+use super::models::rules::Rules;
+use super::models::suggest::Classifier;
 use super::models::*;
 
 macro_rules! get_foo {
@@ -27,7 +34,7 @@ macro_rules! get_foo {
             .next()?
     };
     (item $state:expr, $item_id:expr) => {
-        $state.grouped.iter().filter(|x| x.key == $item_id).next()?
+        $state.grouped.iter().find(|x| x.key == $item_id)?
     };
     (scan mut $state:expr, $scan_id:expr) => {
         $state
@@ -37,7 +44,7 @@ macro_rules! get_foo {
             .next()?
     };
     (scan $state:expr, $scan_id:expr) => {
-        $state.iter().filter(|x| x.id == $scan_id).next()?
+        $state.iter().find(|x| x.id == $scan_id)?
     };
     (state $state:expr) => {
         get_mutexed($state)
@@ -45,15 +52,15 @@ macro_rules! get_foo {
 }
 
 #[derive(Serialize, PartialEq, PartialOrd, Ord, Eq, Clone, Debug)]
-struct Account {
-    full: String,
+pub(super) struct Account {
+    pub(super) full: String,
     segments: Vec<(String, &'static str)>,
 }
 
 #[derive(Serialize, Debug, Clone)]
-struct FavaAccounts {
-    accounts: Vec<Account>,
-    pay_options: Vec<Account>,
+pub(super) struct FavaAccounts {
+    pub(super) accounts: Vec<Account>,
+    pub(super) pay_options: Vec<Account>,
 }
 
 const COLORS: &[&'static str] = &[
@@ -143,9 +150,42 @@ struct CategoriseForm<'r> {
 }
 
 #[get("/")]
-fn get(scans: &State<Scans>, user: AuthUser, mut ctx: Context) -> Result<Template, Redirect> {
+fn get(
+    scans: &State<Scans>,
+    imports: &State<Imports>,
+    user: AuthUser,
+    mut ctx: Context,
+) -> Result<Template, Redirect> {
     user.check()?;
+    let imports: Vec<_> = imports.with(|imports| {
+        imports
+            .iter()
+            .map(|import| {
+                json!({
+                    "id": import.id,
+                    "bank": import.bank,
+                    "valid": import.statements.len(),
+                    "failed": import.errors.len(),
+                })
+            })
+            .collect()
+    });
+
     scans.with(|scans| {
+        // Scans from the import folder that still need work
+        let waiting = scans
+            .iter()
+            .filter(|scan| {
+                let (done, total) = scan.count_done();
+                scan.source.is_some() && done < total
+            })
+            .count();
+        let waiting = match waiting {
+            0 => None,
+            1 => Some("1 new scan waiting".to_string()),
+            n => Some(format!("{} new scans waiting", n)),
+        };
+
         let scans: Vec<_> = scans
             .iter()
             .map(|scan| {
@@ -153,63 +193,217 @@ fn get(scans: &State<Scans>, user: AuthUser, mut ctx: Context) -> Result<Templat
                     "done": scan.count_done().0,
                     "total": scan.count_done().1,
                     "id": scan.id,
+                    "source": scan.source,
                 })
             })
             .collect();
 
+        let banks: Vec<_> = importer::names().collect();
+
         ctx.merge(json!({
         "scans": scans,
-
+        "waiting": waiting,
+        "imports": imports,
+        "banks": banks,
         }));
         Ok(Template::render("fava/ingest/index", &ctx.value()))
     })
 }
 
-#[post("/new", data = "<data>")]
+/// Creates a scan from freshly imported statements, flagging the ones that are already known
+/// and booking the ones that pay an invoice or are transfers with our other accounts
+pub(super) fn push_scan(
+    items: Vec<Statement>,
+    scans: &Scans,
+    rules: &Rules,
+    aliases: &Aliases,
+    invoices: &Invoices,
+    own: &OwnAccounts,
+    config: &ScanConfigConfig,
+) -> String {
+    let mut scan = rules
+        .with(|rules| aliases.with(|aliases| Scan::new(items, rules, aliases, config.grouping)));
+    let ledger = ledger::read(&config.beancount_location);
+
+    scans.with_save(|r| {
+        scan.flag_duplicates(r, &ledger);
+        let matched = invoices::matched(r);
+        invoices.with(|invoices| scan.match_invoices(invoices, &matched));
+        let id = scan.id.clone();
+        r.push(scan);
+        own.with(|own| transfers::merge_transfers(r, own));
+        id
+    })
+}
+
+#[post("/new?<bank>", data = "<data>")]
+#[allow(clippy::too_many_arguments)]
 async fn new_post(
     data: Data<'_>,
+    bank: Option<&str>,
     scans: &State<Scans>,
+    imports: &State<Imports>,
+    rules: &State<Rules>,
+    aliases: &State<Aliases>,
+    invoices: &State<Invoices>,
+    own: &State<OwnAccounts>,
+    config: &State<ScanConfigConfig>,
+    mut ctx: Context,
     user: AuthUser,
-) -> Result<Option<Redirect>, Redirect> {
-    user.check()?;
+) -> Result<Redirect, Template> {
+    if let Err(e) = user.check() {
+        return Ok(e);
+    }
 
     let mut buf = Vec::new();
-    data.open(512u32.megabytes()).read_to_end(&mut buf).await
-        .map_err(|e| {
-            eprintln!("{:?}", e);
-            Redirect::to("/fava")
-        })?;
-    
+    if let Err(e) = data.open(512u32.megabytes()).read_to_end(&mut buf).await {
+        eprintln!("{:?}", e);
+        return Ok(Redirect::to("/fava"));
+    }
+
     let string = String::from_utf8_lossy(&buf);
-        // .read_to_string(&mut string)
-        // .await
+    let bank = bank.filter(|x| !x.is_empty());
+
+    let import = importer::read(&string, bank).map_err(|e| {
+        ctx.add("error", e.to_string());
+        Template::render("error", ctx.value())
+    })?;
+
+    if import.errors.is_empty() {
+        push_scan(
+            import.statements,
+            scans,
+            rules,
+            aliases,
+            invoices,
+            own,
+            config,
+        );
+        Ok(Redirect::to("/fava"))
+    } else {
+        let id = import.id.clone();
+        imports.with_save(|r| r.push(import));
+        Ok(Redirect::to(format!("/fava/ingest/import/{}", id)))
+    }
+}
+
+#[get("/import/<import_id>")]
+fn get_import(
+    import_id: &str,
+    imports: &State<Imports>,
+    mut ctx: Context,
+    user: AuthUser,
+) -> Option<Result<Template, Redirect>> {
+    if let Err(e) = user.check() {
+        return Some(Err(e));
+    }
+
+    imports.with(|imports| {
+        let import = imports.iter().find(|x| x.id == import_id)?;
+        ctx.merge(json!({
+            "import": import,
+            "valid": import.statements.len(),
+            "failed": import.errors.len(),
+        }));
+        Some(Ok(Template::render("fava/ingest/import", ctx.value())))
+    })
+}
+
+#[derive(FromForm)]
+struct RowForm<'r> {
+    raw: &'r str,
+}
+
+/// Retry a hand fixed row
+#[post("/import/<import_id>/<line>", data = "<user_input>")]
+fn post_import_row(
+    import_id: &str,
+    line: u64,
+    user_input: Form<RowForm<'_>>,
+    imports: &State<Imports>,
+    user: AuthUser,
+) -> Redirect {
+    if let Err(e) = user.check() {
+        return e;
+    }
+
+    imports.with_save(|imports| {
+        if let Some(import) = imports.iter_mut().find(|x| x.id == import_id) {
+            if let Err(e) = import.retry(line, user_input.raw) {
+                eprintln!("{}", e);
+            }
+        }
+    });
+
+    Redirect::to(format!("/fava/ingest/import/{}", import_id))
+}
 
-    // let string = data
-    //     .open(512u32.megabytes())
-    //     .into_string()
-    //     .await
-    //     .map_err(|e| {
-    //         eprintln!("{:?}", e);
-    //         Redirect::to("/fava")
-    //     })?
-    //     .into_inner();
+/// Skip a broken row
+#[delete("/import/<import_id>/<line>")]
+fn delete_import_row(
+    import_id: &str,
+    line: u64,
+    imports: &State<Imports>,
+    user: AuthUser,
+) -> Redirect {
+    if let Err(e) = user.check() {
+        return e;
+    }
 
-    let mut cursor = Cursor::new(string.replace(",", "."));
+    imports.with_save(|imports| {
+        if let Some(import) = imports.iter_mut().find(|x| x.id == import_id) {
+            import.skip(line);
+        }
+    });
 
-    let mut rdr = csv::ReaderBuilder::new()
-        .delimiter(b';')
-        .from_reader(&mut cursor);
+    Redirect::to(format!("/fava/ingest/import/{}", import_id))
+}
 
-    let mut items = Vec::new();
-    for result in rdr.deserialize() {
-        let record: StatementUgly = result.map_err(|_| Redirect::to("/fava"))?;
-        items.push(record.into());
+/// Accept the valid rows as a scan, rows that still fail are dropped
+#[post("/import/<import_id>")]
+#[allow(clippy::too_many_arguments)]
+fn post_import(
+    import_id: &str,
+    imports: &State<Imports>,
+    scans: &State<Scans>,
+    rules: &State<Rules>,
+    aliases: &State<Aliases>,
+    invoices: &State<Invoices>,
+    own: &State<OwnAccounts>,
+    config: &State<ScanConfigConfig>,
+    user: AuthUser,
+) -> Option<Redirect> {
+    if let Err(e) = user.check() {
+        return Some(e);
     }
-    let scan = Scan::new(items);
 
-    scans.with_save(|r| r.push(scan));
+    let import = imports.with_save(|imports| {
+        let index = imports.iter().position(|x| x.id == import_id)?;
+        Some(imports.remove(index))
+    })?;
 
-    Ok(Some(Redirect::to("/fava")))
+    let id = push_scan(
+        import.statements,
+        scans,
+        rules,
+        aliases,
+        invoices,
+        own,
+        config,
+    );
+
+    Some(Redirect::to(format!("/fava/ingest/{}", id)))
+}
+
+#[delete("/import/<import_id>")]
+fn delete_import(import_id: &str, imports: &State<Imports>, user: AuthUser) -> Redirect {
+    if let Err(e) = user.check() {
+        return e;
+    }
+
+    imports.with_save(|imports| imports.retain(|x| x.id != import_id));
+
+    Redirect::to("/fava/ingest")
 }
 
 #[get("/<uuid>")]
@@ -217,6 +411,7 @@ fn get_scan(
     mut context: Context,
     uuid: &str,
     scans: &State<Scans>,
+    own: &State<OwnAccounts>,
     accounts: &State<FavaAccounts>,
     user: AuthUser,
 ) -> Option<Result<Template, Redirect>> {
@@ -225,10 +420,12 @@ fn get_scan(
     scans.with(|state| {
         let scan = get_foo!(scan state, uuid);
 
-        if let Some(item) = scan.get_first() {
+        if scan.duplicates().next().is_some() {
+            Err(Redirect::to(uri!("/fava/ingest", get_duplicates(uuid)))).into()
+        } else if let Some(item) = scan.get_first() {
             Err(Redirect::to(uri!(
                 "/fava/ingest",
-                get_one(uuid, item.key.to_string())
+                get_one(uuid, item.key.to_string(), _)
             )))
             .into()
         } else {
@@ -244,11 +441,31 @@ fn get_scan(
                 },
             );
 
-            let total: usize = scan.grouped.iter().map(|x| x.statements.len()).sum();
+            let total = scan.count_done().1;
+            let own_accounts: Vec<_> = own.with(|own| {
+                scan.own_accounts()
+                    .into_iter()
+                    .map(|(number, count)| {
+                        json!({
+                            "iban": iban::format(&number),
+                            "account": own_accounts::lookup(own, &number),
+                            "count": count,
+                        })
+                    })
+                    .collect()
+            });
+            let exporters: Vec<_> = exporter::EXPORTERS
+                .iter()
+                .map(|x| json!({ "name": x.name(), "label": x.label() }))
+                .collect();
 
             // TODO
             let add = json! {{
+                "scan_id": uuid,
+                "deleted": scan.trash().count(),
                 "pay_options": accounts.pay_options,
+                "exporters": exporters,
+                "own_accounts": own_accounts,
                 "total": total,
                 "per_category": per_category,
             }};
@@ -265,13 +482,82 @@ struct Payment<'r> {
     pay: &'r str,
 }
 
-use std::io::Write;
+/// Lines of the current ledger shown above the new transactions
+const PREVIEW_CONTEXT: usize = 5;
+
+#[get("/<scan_id>/preview?<pay>&<error>", rank = 1)]
+#[allow(clippy::too_many_arguments)]
+fn get_preview(
+    scan_id: &str,
+    pay: &str,
+    error: Option<&str>,
+    scans: &State<Scans>,
+    own: &State<OwnAccounts>,
+    config: &State<ScanConfigConfig>,
+    mut context: Context,
+    user: AuthUser,
+) -> Option<Result<Template, Redirect>> {
+    if let Err(e) = user.check() {
+        return Some(Err(e));
+    }
+
+    scans.with(|scans| {
+        let scan = get_foo!(scan scans, scan_id);
+        if !scan.is_ready() {
+            return Some(Err(Redirect::to(uri!("/fava/ingest", get_scan(&scan.id)))));
+        }
+
+        let existing = fs::read_to_string(&config.beancount_location).unwrap_or_default();
+        let mut before: Vec<_> = existing.lines().rev().take(PREVIEW_CONTEXT).collect();
+        before.reverse();
+        let opened = ledger::opened(&existing);
+
+        let addition = own.with(|own| scan.to_ledger(pay, own, &config.output, &opened));
+        let added: Vec<_> = addition.lines().collect();
+
+        let errors: Vec<_> = error
+            .into_iter()
+            .flat_map(|x| x.lines())
+            .map(|e| Error::new("Invalid ledger", e))
+            .collect();
+
+        context.merge(json!({
+            "errors": errors,
+            "scan_id": scan_id,
+            "pay": pay,
+            "location": config.beancount_location,
+            "before": before,
+            "added": added,
+            "total": scan.count_done().1,
+        }));
+
+        Some(Ok(Template::render("fava/ingest/preview", context.value())))
+    })
+}
+
+/// Appends a categorised scan to the ledger, paid from `pay`, the invoices it pays are paid.
+/// The ledger is left untouched when the check finds errors, returns the backup of the old one.
+pub(super) fn append_scan(
+    scan: &Scan,
+    pay: &str,
+    own: &OwnAccounts,
+    invoices: &Invoices,
+    config: &ScanConfigConfig,
+) -> Result<Option<String>, Vec<String>> {
+    let opened = ledger::read_opened(&config.beancount_location);
+    let addition = own.with(|own| scan.to_ledger(pay, own, &config.output, &opened));
+    let backup = ledger::append(&config.beancount_location, &addition, &config.bean_check)?;
+    invoices.with_save(|invoices| invoices::settle(invoices, scan));
+    Ok(backup)
+}
 
 #[post("/<scan_id>", data = "<user_input>")]
 fn post_scan(
     scan_id: &str,
     user_input: Form<Payment<'_>>,
     scans: &State<Scans>,
+    own: &State<OwnAccounts>,
+    invoices: &State<Invoices>,
     config: &State<ScanConfigConfig>,
     user: AuthUser,
 ) -> Option<Redirect> {
@@ -279,28 +565,20 @@ fn post_scan(
         return Some(e);
     }
 
-    let location = &config.beancount_location;
-
     scans.with_save(|scans| {
         let scan_index = scans.iter().position(|x| x.id == scan_id)?;
-        let scan = scans.get_mut(scan_index)?;
-
-        let mut file = fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(location)
-            .ok()?;
-
-        let mut items: Vec<_> = scan
-            .grouped
-            .iter()
-            .flat_map(|x| x.statements.iter().cloned())
-            .collect();
+        let scan = scans.get(scan_index)?;
+        if !scan.is_ready() {
+            return Redirect::to(uri!("/fava/ingest", get_scan(&scan.id))).into();
+        }
 
-        items.sort_by_key(|x| x.date);
-        for item in items.into_iter() {
-            let output = item.to_output(user_input.pay);
-            writeln!(file, "\n{}", output).ok()?;
+        if let Err(errors) = append_scan(scan, user_input.pay, own, invoices, config) {
+            let error = errors.join("\n");
+            return Redirect::to(uri!(
+                "/fava/ingest",
+                get_preview(&scan.id, user_input.pay, Some(error))
+            ))
+            .into();
         }
 
         scans.remove(scan_index);
@@ -308,12 +586,119 @@ fn post_scan(
     })
 }
 
-#[get("/<scan_id>/<item_id>")]
+/// A file that the browser saves instead of showing
+#[derive(Responder)]
+struct Download {
+    inner: (ContentType, String),
+    disposition: Header<'static>,
+}
+
+#[get("/<scan_id>/export/<format>?<pay>", rank = 1)]
+fn export(
+    scan_id: &str,
+    format: &str,
+    pay: &str,
+    scans: &State<Scans>,
+    own: &State<OwnAccounts>,
+    config: &State<ScanConfigConfig>,
+    user: AuthUser,
+) -> Option<Result<Download, Redirect>> {
+    if let Err(e) = user.check() {
+        return Some(Err(e));
+    }
+    let exporter = exporter::by_name(format)?;
+
+    scans.with(|scans| {
+        let scan = get_foo!(scan scans, scan_id);
+        if !scan.is_ready() {
+            return Some(Err(Redirect::to(uri!("/fava/ingest", get_scan(&scan.id)))));
+        }
+
+        let content = own.with(|own| scan.export(exporter, pay, own, &config.output));
+        let content_type =
+            ContentType::from_extension(exporter.extension()).unwrap_or(ContentType::Plain);
+        let disposition = format!(
+            "attachment; filename=\"{}.{}\"",
+            scan.id,
+            exporter.extension()
+        );
+
+        Some(Ok(Download {
+            inner: (content_type, content),
+            disposition: Header::new("Content-Disposition", disposition),
+        }))
+    })
+}
+
+#[get("/<scan_id>/duplicates", rank = 1)]
+fn get_duplicates(
+    scan_id: &str,
+    scans: &State<Scans>,
+    mut context: Context,
+    user: AuthUser,
+) -> Option<Result<Template, Redirect>> {
+    if let Err(e) = user.check() {
+        return Some(Err(e));
+    }
+
+    scans.with(|state| {
+        let scan = get_foo!(scan state, scan_id);
+        let duplicates: Vec<_> = scan
+            .duplicates()
+            .map(|(group, item)| {
+                json!({
+                    "group": group.key,
+                    "item": item,
+                    "keep": uri!("/fava/ingest", keep_one(&scan.id, &group.key, &item.id.0)).to_string(),
+                    "drop": uri!("/fava/ingest", delete_one(&scan.id, &group.key, &item.id.0)).to_string(),
+                })
+            })
+            .collect();
+
+        context.merge(json!({
+            "scan_id": scan_id,
+            "duplicates": duplicates,
+        }));
+        Some(Ok(Template::render(
+            "fava/ingest/duplicates",
+            context.value(),
+        )))
+    })
+}
+
+/// Keep a suspected duplicate, it is a genuine statement
+#[post("/<scan_id>/<group_id>/<item_id>/keep")]
+fn keep_one(
+    scan_id: &str,
+    group_id: &str,
+    item_id: &str,
+    scans: &State<Scans>,
+    user: AuthUser,
+) -> Redirect {
+    if let Err(e) = user.check() {
+        return e;
+    }
+
+    scans.with_save(|scans| {
+        if let Some(scan) = scans.iter_mut().find(|x| x.id == scan_id) {
+            scan.edit("Keep a suspected duplicate", |scan| {
+                scan.keep(group_id, item_id)
+            });
+        }
+    });
+
+    Redirect::to(format!("/fava/ingest/{}", scan_id))
+}
+
+#[get("/<scan_id>/<item_id>?<error>", rank = 2)]
+#[allow(clippy::too_many_arguments)]
 fn get_one(
     scan_id: &str,
     item_id: &str,
+    error: Option<&str>,
     scans: &State<Scans>,
     accounts: &State<FavaAccounts>,
+    classifier: &State<Classifier>,
     mut context: Context,
     user: AuthUser,
 ) -> Option<Result<Template, Redirect>> {
@@ -323,11 +708,85 @@ fn get_one(
         let scan = get_foo!(scan state, scan_id);
         let item = get_foo!(item scan, item_id);
 
+        // Likely accounts go first, the others stay in alphabetical order
+        let suggestions: Vec<_> = item
+            .suggest(classifier)
+            .into_iter()
+            .filter_map(|suggestion| {
+                let account = accounts
+                    .accounts
+                    .iter()
+                    .find(|x| x.full == suggestion.account)?;
+                Some((account, (suggestion.confidence * 100.0).round() as usize))
+            })
+            .collect();
+        let others: Vec<_> = accounts
+            .accounts
+            .iter()
+            .filter(|x| !suggestions.iter().any(|(s, _)| s.full == x.full))
+            .collect();
+        let suggestions: Vec<_> = suggestions
+            .into_iter()
+            .map(|(account, confidence)| json!({"account": account, "confidence": confidence}))
+            .collect();
+
+        let groups: Vec<_> = scan
+            .grouped
+            .iter()
+            .map(|x| &x.key)
+            .filter(|x| *x != &item.key && *x != "deleted")
+            .collect();
+        let strategies: Vec<_> = GroupingStrategy::ALL.iter().map(|(x, _)| x).collect();
+
+        // Counterparty accounts as printed, flagged when the checksum is wrong
+        let ibans: HashMap<_, _> = item
+            .statements
+            .iter()
+            .filter_map(|x| {
+                let account = x.counter_account.as_deref()?;
+                let valid = x.iban();
+                let json = json!({
+                    "iban": valid.as_deref().map(iban::format).unwrap_or_else(|| account.to_string()),
+                    "valid": valid.is_some(),
+                    "bic": x.bic(),
+                });
+                Some((x.id.0.clone(), json))
+            })
+            .collect();
+
+        // Structured communications as printed, flagged when the check digits are wrong
+        let ogms: HashMap<_, _> = item
+            .statements
+            .iter()
+            .filter_map(|x| {
+                let text = x.structured()?;
+                let valid = x.ogm();
+                let json = json!({
+                    "ogm": valid.as_deref().map(ogm::format).unwrap_or_else(|| text.to_string()),
+                    "valid": valid.is_some(),
+                });
+                Some((x.id.0.clone(), json))
+            })
+            .collect();
+
         let total = item.total();
+        let errors: Vec<_> = error
+            .map(|e| Error::new("Invalid split", e))
+            .into_iter()
+            .collect();
+        // Group keys are payees, they need encoding before they go into a URL
+        let group_url = uri!("/fava/ingest", get_one(&scan.id, &item.key, _)).to_string();
         let items = json!({
-            "errors": [],
+            "errors": errors,
+            "scan_id": scan_id,
+            "group_url": group_url,
+            "groups": groups,
+            "strategies": strategies,
             "item": item,
-            "accounts": accounts.accounts,
+            "ibans": ibans,
+            "ogms": ogms,
+            "suggestions": suggestions,
+            "accounts": others,
             "total": total
         });
 
@@ -336,7 +795,7 @@ fn get_one(
     })
 }
 
-#[post("/<scan_id>/<item_id>", data = "<user_input>")]
+#[post("/<scan_id>/<item_id>", data = "<user_input>", rank = 2)]
 fn post_one(
     scan_id: &str,
     item_id: &str,
@@ -349,15 +808,374 @@ fn post_one(
     }
 
     scans.with_save(|scans| {
-        if let Some(scan) = scans.iter_mut().filter(|x| x.id == scan_id).next() {
-            scan.categorise(item_id, user_input.category);
+        if let Some(scan) = scans.iter_mut().find(|x| x.id == scan_id) {
+            scan.edit(
+                format!("Categorise {} as {}", item_id, user_input.category),
+                |scan| scan.categorise(item_id, user_input.category),
+            );
         }
 
         Redirect::to(format!("/fava/ingest/{}", scan_id))
     })
 }
 
-#[delete("/<scan_id>/<item_id>")]
+/// Back to the item view of a group, group keys need escaping
+fn group_redirect(scan: &str, group: &str, message: Option<&str>) -> Redirect {
+    Redirect::to(uri!("/fava/ingest", get_one(scan, group, message)))
+}
+
+#[get("/<scan_id>/trash", rank = 1)]
+fn get_trash(
+    scan_id: &str,
+    scans: &State<Scans>,
+    mut context: Context,
+    user: AuthUser,
+) -> Option<Result<Template, Redirect>> {
+    if let Err(e) = user.check() {
+        return Some(Err(e));
+    }
+
+    scans.with(|state| {
+        let scan = get_foo!(scan state, scan_id);
+        let items: Vec<_> = scan.trash().collect();
+
+        let mut deleted_groups: Vec<_> = items.iter().flat_map(|x| &x.deleted_from).collect();
+        deleted_groups.sort();
+        deleted_groups.dedup();
+
+        let groups: Vec<_> = scan
+            .grouped
+            .iter()
+            .map(|x| &x.key)
+            .filter(|x| *x != "deleted")
+            .collect();
+
+        context.merge(json!({
+            "scan_id": scan_id,
+            "items": items,
+            "deleted_groups": deleted_groups,
+            "groups": groups,
+        }));
+        Some(Ok(Template::render("fava/ingest/trash", context.value())))
+    })
+}
+
+#[derive(FromForm)]
+struct RestoreForm<'r> {
+    item: Option<&'r str>,
+    group: Option<&'r str>,
+    to: Option<&'r str>,
+}
+
+/// Restore a deleted statement, or all statements of a deleted group
+#[post("/<scan_id>/trash", data = "<user_input>", rank = 1)]
+fn restore(
+    scan_id: &str,
+    user_input: Form<RestoreForm<'_>>,
+    scans: &State<Scans>,
+    user: AuthUser,
+) -> Redirect {
+    if let Err(e) = user.check() {
+        return e;
+    }
+
+    scans.with_save(|scans| {
+        if let Some(scan) = scans.iter_mut().find(|x| x.id == scan_id) {
+            match (user_input.item, user_input.group) {
+                (Some(item), _) => scan.edit("Restore a statement", |scan| {
+                    scan.restore(item, user_input.to)
+                }),
+                (None, Some(group)) => scan.edit(format!("Restore {}", group), |scan| {
+                    scan.restore_group(group)
+                }),
+                (None, None) => {}
+            }
+        }
+    });
+
+    Redirect::to(format!("/fava/ingest/{}/trash", scan_id))
+}
+
+#[get("/<scan_id>/history", rank = 1)]
+fn get_history(
+    scan_id: &str,
+    scans: &State<Scans>,
+    mut context: Context,
+    user: AuthUser,
+) -> Option<Result<Template, Redirect>> {
+    if let Err(e) = user.check() {
+        return Some(Err(e));
+    }
+
+    scans.with(|state| {
+        let scan = get_foo!(scan state, scan_id);
+        // Only what the page shows, the events also hold the groups to go back to
+        let view = |x: &history::Event| json!({"time": x.time, "action": x.action});
+        let done: Vec<_> = scan.history.done.iter().rev().map(view).collect();
+        let undone: Vec<_> = scan.history.undone.iter().rev().map(view).collect();
+
+        context.merge(json!({
+            "scan_id": scan_id,
+            "done": done,
+            "undone": undone,
+        }));
+        Some(Ok(Template::render("fava/ingest/history", context.value())))
+    })
+}
+
+#[post("/<scan_id>/undo", rank = 1)]
+fn undo(scan_id: &str, scans: &State<Scans>, user: AuthUser) -> Redirect {
+    if let Err(e) = user.check() {
+        return e;
+    }
+
+    scans.with_save(|scans| {
+        if let Some(scan) = scans.iter_mut().find(|x| x.id == scan_id) {
+            scan.undo();
+        }
+    });
+
+    Redirect::to(format!("/fava/ingest/{}", scan_id))
+}
+
+#[post("/<scan_id>/redo", rank = 1)]
+fn redo(scan_id: &str, scans: &State<Scans>, user: AuthUser) -> Redirect {
+    if let Err(e) = user.check() {
+        return e;
+    }
+
+    scans.with_save(|scans| {
+        if let Some(scan) = scans.iter_mut().find(|x| x.id == scan_id) {
+            scan.redo();
+        }
+    });
+
+    Redirect::to(format!("/fava/ingest/{}", scan_id))
+}
+
+#[derive(FromForm)]
+struct MergeForm<'r> {
+    into: &'r str,
+}
+
+/// Move all statements of a group into another group
+#[post("/<scan_id>/<group_id>/merge", data = "<user_input>", rank = 1)]
+fn merge_group(
+    scan_id: &str,
+    group_id: &str,
+    user_input: Form<MergeForm<'_>>,
+    scans: &State<Scans>,
+    user: AuthUser,
+) -> Redirect {
+    if let Err(e) = user.check() {
+        return e;
+    }
+
+    scans.with_save(|scans| {
+        if let Some(scan) = scans.iter_mut().find(|x| x.id == scan_id) {
+            scan.edit(
+                format!("Merge {} into {}", group_id, user_input.into),
+                |scan| scan.merge(group_id, user_input.into),
+            );
+        }
+    });
+
+    group_redirect(scan_id, user_input.into, None)
+}
+
+#[derive(FromForm)]
+struct MoveForm<'r> {
+    to: &'r str,
+}
+
+/// Move a statement to another group, a new group name splits it off
+#[post("/<scan_id>/<group_id>/<item_id>/move", data = "<user_input>")]
+fn move_item(
+    scan_id: &str,
+    group_id: &str,
+    item_id: &str,
+    user_input: Form<MoveForm<'_>>,
+    scans: &State<Scans>,
+    user: AuthUser,
+) -> Redirect {
+    if let Err(e) = user.check() {
+        return e;
+    }
+
+    let group_left = scans.with_save(|scans| {
+        let scan = scans.iter_mut().find(|x| x.id == scan_id)?;
+        scan.edit(
+            format!("Move a statement from {} to {}", group_id, user_input.to),
+            |scan| scan.move_item(group_id, item_id, user_input.to),
+        );
+        scan.grouped.iter().find(|x| x.key == group_id).map(|_| ())
+    });
+
+    match group_left {
+        Some(()) => group_redirect(scan_id, group_id, None),
+        None => group_redirect(scan_id, user_input.to.trim(), None),
+    }
+}
+
+#[derive(FromForm)]
+struct RegroupForm<'r> {
+    strategy: &'r str,
+}
+
+/// Throws away the current groups and groups the statements again
+#[post("/<scan_id>/regroup", data = "<user_input>", rank = 1)]
+fn regroup(
+    scan_id: &str,
+    user_input: Form<RegroupForm<'_>>,
+    scans: &State<Scans>,
+    user: AuthUser,
+) -> Redirect {
+    if let Err(e) = user.check() {
+        return e;
+    }
+
+    if let Some(strategy) = GroupingStrategy::by_name(user_input.strategy) {
+        scans.with_save(|scans| {
+            if let Some(scan) = scans.iter_mut().find(|x| x.id == scan_id) {
+                scan.edit(format!("Regroup by {}", user_input.strategy), |scan| {
+                    scan.regroup(strategy)
+                });
+            }
+        });
+    }
+
+    Redirect::to(format!("/fava/ingest/{}", scan_id))
+}
+
+#[derive(FromForm)]
+struct SplitForm<'r> {
+    account: &'r str,
+    share: &'r str,
+}
+
+/// Send part of a statement to another account
+#[post("/<scan_id>/<group_id>/<item_id>/split", data = "<user_input>")]
+fn post_split(
+    scan_id: &str,
+    group_id: &str,
+    item_id: &str,
+    user_input: Form<SplitForm<'_>>,
+    scans: &State<Scans>,
+    user: AuthUser,
+) -> Redirect {
+    if let Err(e) = user.check() {
+        return e;
+    }
+
+    let result = scans.with_save(|scans| {
+        let scan = scans
+            .iter_mut()
+            .find(|x| x.id == scan_id)
+            .ok_or_else(|| "No such scan.".to_string())?;
+        let share = user_input.share.parse::<split::Share>()?;
+        scan.edit(
+            format!("Split {} to {}", user_input.share, user_input.account),
+            |scan| {
+                scan.statement_mut(group_id, item_id)
+                    .ok_or_else(|| "No such statement.".to_string())?
+                    .add_split(user_input.account, share)
+            },
+        )
+    });
+
+    group_redirect(scan_id, group_id, result.err().as_deref())
+}
+
+#[delete("/<scan_id>/<group_id>/<item_id>/split/<index>")]
+fn delete_split(
+    scan_id: &str,
+    group_id: &str,
+    item_id: &str,
+    index: usize,
+    scans: &State<Scans>,
+    user: AuthUser,
+) -> Redirect {
+    if let Err(e) = user.check() {
+        return e;
+    }
+
+    scans.with_save(|scans| {
+        if let Some(scan) = scans.iter_mut().find(|x| x.id == scan_id) {
+            scan.edit("Remove a split", |scan| {
+                if let Some(statement) = scan.statement_mut(group_id, item_id) {
+                    statement.remove_split(index);
+                }
+            });
+        }
+    });
+
+    group_redirect(scan_id, group_id, None)
+}
+
+#[derive(FromForm)]
+struct ShareForm<'r> {
+    people: &'r str,
+}
+
+/// Share a statement with other people, they owe us their part
+#[post("/<scan_id>/<group_id>/<item_id>/share", data = "<user_input>")]
+fn share_item(
+    scan_id: &str,
+    group_id: &str,
+    item_id: &str,
+    user_input: Form<ShareForm<'_>>,
+    scans: &State<Scans>,
+    user: AuthUser,
+) -> Redirect {
+    if let Err(e) = user.check() {
+        return e;
+    }
+
+    let result = shared::parse(user_input.people).and_then(|people| {
+        scans.with_save(|scans| {
+            let scan = scans
+                .iter_mut()
+                .find(|x| x.id == scan_id)
+                .ok_or_else(|| "No such scan.".to_string())?;
+            scan.edit(format!("Share with {}", user_input.people), |scan| {
+                scan.statement_mut(group_id, item_id)
+                    .ok_or_else(|| "No such statement.".to_string())
+                    .map(|x| x.share(&people))
+            })
+        })
+    });
+
+    group_redirect(scan_id, group_id, result.err().as_deref())
+}
+
+/// Share every statement of a group the same way
+#[post("/<scan_id>/<group_id>/share", data = "<user_input>", rank = 1)]
+fn share_group(
+    scan_id: &str,
+    group_id: &str,
+    user_input: Form<ShareForm<'_>>,
+    scans: &State<Scans>,
+    user: AuthUser,
+) -> Redirect {
+    if let Err(e) = user.check() {
+        return e;
+    }
+
+    let result = shared::parse(user_input.people).map(|people| {
+        scans.with_save(|scans| {
+            if let Some(scan) = scans.iter_mut().find(|x| x.id == scan_id) {
+                scan.edit(
+                    format!("Share {} with {}", group_id, user_input.people),
+                    |scan| scan.share_group(group_id, &people),
+                );
+            }
+        })
+    });
+
+    group_redirect(scan_id, group_id, result.err().as_deref())
+}
+
+#[delete("/<scan_id>/<item_id>", rank = 2)]
 fn delete_group(scan_id: &str, item_id: &str, scans: &State<Scans>, user: AuthUser) -> Redirect {
     if let Err(e) = user.check() {
         return e;
@@ -365,8 +1183,8 @@ fn delete_group(scan_id: &str, item_id: &str, scans: &State<Scans>, user: AuthUs
 
     println!("Deleting");
     scans.with_save(|scans| {
-        if let Some(scan) = scans.iter_mut().filter(|x| x.id == scan_id).next() {
-            scan.delete(item_id);
+        if let Some(scan) = scans.iter_mut().find(|x| x.id == scan_id) {
+            scan.edit(format!("Delete {}", item_id), |scan| scan.delete(item_id));
         }
     });
 
@@ -387,8 +1205,10 @@ fn delete_one(
 
     println!("Deleting {} {} {}", scan_id, group_id, item_id);
     scans.with_save(|scans| {
-        if let Some(scan) = scans.iter_mut().filter(|x| x.id == scan_id).next() {
-            scan.delete_item(group_id, item_id);
+        if let Some(scan) = scans.iter_mut().find(|x| x.id == scan_id) {
+            scan.edit(format!("Delete a statement of {}", group_id), |scan| {
+                scan.delete_item(group_id, item_id)
+            });
         }
     });
 
@@ -404,19 +1224,44 @@ pub fn fuel(rocket: Rocket<Build>) -> Rocket<Build> {
                 get,
                 new_post,
                 get_scan,
+                get_preview,
                 post_scan,
+                export,
                 get_one,
                 post_one,
                 delete_group,
-                delete_one
+                delete_one,
+                get_import,
+                post_import,
+                delete_import,
+                post_import_row,
+                delete_import_row,
+                get_duplicates,
+                keep_one,
+                post_split,
+                delete_split,
+                share_item,
+                share_group,
+                merge_group,
+                move_item,
+                regroup,
+                get_trash,
+                restore,
+                get_history,
+                undo,
+                redo
             ],
         )
         .attach(AdHoc::config::<ScanConfigConfig>())
         .attach(AdHoc::try_on_ignite("beans", |rocket| {
             Box::pin(async move {
                 if let Some(config) = rocket.state::<ScanConfigConfig>() {
-                    let accounts = FavaAccounts::init(&config);
-                    Ok(rocket.manage(accounts))
+                    let accounts = FavaAccounts::init(config);
+                    let classifier =
+                        Classifier::train(&ledger::read(&config.beancount_location), |account| {
+                            !accounts.pay_options.iter().any(|x| x.full == account)
+                        });
+                    Ok(rocket.manage(accounts).manage(classifier))
                 } else {
                     Err(rocket)
                 }
@@ -427,4 +1272,9 @@ pub fn fuel(rocket: Rocket<Build>) -> Rocket<Build> {
             |c: &ScanConfigConfig| c.ingest_file_location.to_string(),
             vec![],
         ))
+        .attach(Repository::<Vec<importer::Import>>::adhoc(
+            "imports config",
+            |c: &ScanConfigConfig| c.import_file_location.to_string(),
+            vec![],
+        ))
 }
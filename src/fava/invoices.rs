@@ -0,0 +1,166 @@
+use rocket::form::Form;
+use rocket::serde::json::serde_json::json;
+use rocket::{response::Redirect, routes, Build, Rocket, State};
+use rocket_dyn_templates::Template;
+
+use crate::fava::ScanConfigConfig;
+use crate::repository::Repository;
+use crate::util::Error;
+use crate::{context::Context, oauth::AuthUser};
+
+use super::ingest::FavaAccounts;
+use super::models::invoices::{self, Invoice, Invoices};
+use super::models::{money, ogm, Scans};
+
+/// `matched` are the invoices paid by statements of pending scans
+fn render(
+    mut context: Context,
+    invoices: &[Invoice],
+    matched: &[String],
+    accounts: &FavaAccounts,
+    errors: &[Error],
+) -> Template {
+    // Open invoices go first
+    let mut invoices: Vec<_> = invoices.iter().collect();
+    invoices.sort_by_key(|x| !x.is_open(matched));
+    let invoices: Vec<_> = invoices
+        .into_iter()
+        .map(|x| {
+            json!({
+                "invoice": x,
+                "ogm": ogm::format(&x.ogm),
+                "matched": matched.contains(&x.id),
+            })
+        })
+        .collect();
+
+    context.merge(json!({
+        "invoices": invoices,
+        "accounts": accounts.accounts,
+        "errors": errors,
+    }));
+    Template::render("fava/ingest/invoices", context.value())
+}
+
+#[get("/")]
+fn get(
+    context: Context,
+    invoices: &State<Invoices>,
+    scans: &State<Scans>,
+    accounts: &State<FavaAccounts>,
+    user: AuthUser,
+) -> Result<Template, Redirect> {
+    user.check()?;
+    let matched = scans.with(|scans| invoices::matched(scans));
+    Ok(invoices.with(|invoices| render(context, invoices, &matched, accounts, &[])))
+}
+
+#[derive(FromForm)]
+struct InvoiceForm<'r> {
+    ogm: &'r str,
+    amount: &'r str,
+    account: &'r str,
+    description: &'r str,
+}
+
+impl InvoiceForm<'_> {
+    fn invoice(&self) -> Result<Invoice, Error> {
+        let amount = if self.amount.trim().is_empty() {
+            None
+        } else {
+            let cents = money::parse_cents(self.amount)
+                .map_err(|e| Error::new("Invalid amount", &e.to_string()))?;
+            Some(cents)
+        };
+
+        Invoice::new(
+            self.ogm,
+            amount,
+            self.account.trim(),
+            self.description.trim(),
+        )
+        .map_err(|e| Error::new("Invalid invoice", &e))
+    }
+}
+
+#[post("/", data = "<user_input>")]
+fn post(
+    context: Context,
+    user_input: Form<InvoiceForm<'_>>,
+    invoices: &State<Invoices>,
+    scans: &State<Scans>,
+    accounts: &State<FavaAccounts>,
+    user: AuthUser,
+) -> Result<Redirect, Template> {
+    if let Err(e) = user.check() {
+        return Ok(e);
+    }
+
+    let invoice = user_input.invoice();
+    let matched = scans.with(|scans| invoices::matched(scans));
+    invoices.with_save(|invoices| match invoice {
+        Ok(invoice) => {
+            invoices.push(invoice);
+            Ok(Redirect::to("/fava/ingest/invoices"))
+        }
+        Err(e) => Err(render(context, invoices, &matched, accounts, &[e])),
+    })
+}
+
+#[delete("/<invoice_id>")]
+fn delete(invoice_id: &str, invoices: &State<Invoices>, user: AuthUser) -> Redirect {
+    if let Err(e) = user.check() {
+        return e;
+    }
+
+    invoices.with_save(|invoices| invoices.retain(|x| x.id != invoice_id));
+    Redirect::to("/fava/ingest/invoices")
+}
+
+/// The payment was not the right one, the invoice is open again
+#[post("/<invoice_id>/reopen")]
+fn reopen(invoice_id: &str, invoices: &State<Invoices>, user: AuthUser) -> Redirect {
+    if let Err(e) = user.check() {
+        return e;
+    }
+
+    invoices.with_save(|invoices| {
+        if let Some(invoice) = invoices.iter_mut().find(|x| x.id == invoice_id) {
+            invoice.paid_by = None;
+        }
+    });
+    Redirect::to("/fava/ingest/invoices")
+}
+
+/// Matches the open invoices against the statements of all pending scans
+#[post("/apply")]
+fn apply(invoices: &State<Invoices>, scans: &State<Scans>, user: AuthUser) -> Redirect {
+    if let Err(e) = user.check() {
+        return e;
+    }
+
+    scans.with_save(|scans| {
+        invoices.with(|invoices| {
+            for index in 0..scans.len() {
+                let matched = invoices::matched(scans);
+                scans[index].edit("Match invoices", |scan| {
+                    scan.match_invoices(invoices, &matched)
+                });
+            }
+        })
+    });
+    Redirect::to("/fava/ingest")
+}
+
+pub fn fuel(rocket: Rocket<Build>) -> Rocket<Build> {
+    rocket
+        .mount(
+            "/fava/ingest/invoices",
+            routes![get, post, delete, reopen, apply],
+        )
+        .attach(Repository::<Vec<Invoice>>::adhoc(
+            "invoices config",
+            |c: &ScanConfigConfig| c.invoices_location.to_string(),
+            vec![],
+        ))
+}
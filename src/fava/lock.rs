@@ -0,0 +1,51 @@
+use std::fs::{File, OpenOptions, TryLockError};
+
+use rocket::fairing::AdHoc;
+use rocket::{Build, Rocket};
+
+use crate::fava::ScanConfigConfig;
+
+/// Held while the repositories are in use. The server and the command line keep their own copy
+/// of the JSON files in memory, whoever saves last would silently undo what the other wrote.
+/// The lock goes away with the process, also when it crashes.
+pub struct Lock {
+    _file: File,
+}
+
+impl Lock {
+    pub fn acquire(location: &str) -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(location)
+            .map_err(|e| format!("{}: {}", location, e))?;
+
+        match file.try_lock() {
+            Ok(()) => Ok(Self { _file: file }),
+            Err(TryLockError::WouldBlock) => Err(format!(
+                "{} is locked, the server or another command is using the scans.",
+                location
+            )),
+            Err(TryLockError::Error(e)) => Err(format!("{}: {}", location, e)),
+        }
+    }
+}
+
+pub fn fuel(rocket: Rocket<Build>) -> Rocket<Build> {
+    rocket.attach(AdHoc::try_on_ignite("scans lock", |rocket| {
+        Box::pin(async move {
+            let lock = match rocket.state::<ScanConfigConfig>() {
+                Some(config) => Lock::acquire(&config.lock_location),
+                None => return Err(rocket),
+            };
+            match lock {
+                Ok(lock) => Ok(rocket.manage(lock)),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    Err(rocket)
+                }
+            }
+        })
+    }))
+}
@@ -4,9 +4,17 @@ use rocket_dyn_templates::Template;
 use crate::{context::Context, oauth::AuthUser};
 use rocket::serde::Deserialize;
 
+mod accounts;
+mod aliases;
+pub mod cli;
 mod graphs;
 mod ingest;
+mod invoices;
+mod lock;
 mod models;
+mod rules;
+mod shared;
+mod watch;
 
 #[derive(Deserialize, Debug)]
 struct FavaConfig {
@@ -14,22 +22,80 @@ struct FavaConfig {
     fava_base: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 struct ScanConfigConfig {
     #[serde(default = "default_location")]
     ingest_file_location: String,
     #[serde(default = "default_beancount_location")]
     beancount_location: String,
+    #[serde(default = "default_import_location")]
+    import_file_location: String,
+    #[serde(default = "default_rules_location")]
+    rules_location: String,
+    #[serde(default = "default_accounts_location")]
+    accounts_location: String,
+    #[serde(default = "default_aliases_location")]
+    aliases_location: String,
+    #[serde(default = "default_invoices_location")]
+    invoices_location: String,
+    /// Held by the server or a command while it uses the scans
+    #[serde(default = "default_lock_location")]
+    lock_location: String,
+    /// Validates the ledger before it is replaced, a built-in check is used when it can't be run
+    #[serde(default = "default_bean_check")]
+    bean_check: String,
+    /// Statement files dropped here become scans on their own, nothing is watched when unset
+    #[serde(default)]
+    watch_folder: Option<String>,
+    /// Where imported files are moved to, files that could not be read end up in `failed`
+    #[serde(default = "default_archive_folder")]
+    archive_folder: String,
+    #[serde(default)]
+    grouping: models::GroupingStrategy,
+    #[serde(default)]
+    output: models::output::OutputConfig,
 }
 
 fn default_location() -> String {
     "scan_config.json".to_string()
 }
 
+fn default_import_location() -> String {
+    "imports.json".to_string()
+}
+
+fn default_rules_location() -> String {
+    "rules.json".to_string()
+}
+
+fn default_accounts_location() -> String {
+    "accounts.json".to_string()
+}
+
+fn default_aliases_location() -> String {
+    "aliases.json".to_string()
+}
+
+fn default_invoices_location() -> String {
+    "invoices.json".to_string()
+}
+
+fn default_archive_folder() -> String {
+    "archive".to_string()
+}
+
+fn default_lock_location() -> String {
+    "only_scan.lock".to_string()
+}
+
 fn default_beancount_location() -> String {
     "main.bean".to_string()
 }
 
+fn default_bean_check() -> String {
+    "bean-check".to_string()
+}
+
 fn fava_base() -> String {
     "https://avercruysse.be/fava/".to_string()
 }
@@ -52,7 +118,14 @@ async fn index() -> Redirect {
 
 pub fn fuel(rocket: Rocket<Build>) -> Rocket<Build> {
     let rocket = ingest::fuel(rocket);
+    let rocket = lock::fuel(rocket);
+    let rocket = rules::fuel(rocket);
+    let rocket = accounts::fuel(rocket);
+    let rocket = aliases::fuel(rocket);
+    let rocket = invoices::fuel(rocket);
+    let rocket = shared::fuel(rocket);
     let rocket = graphs::fuel(rocket);
+    let rocket = watch::fuel(rocket);
     rocket
         .mount("/fava", routes![index, beancount])
         .attach(AdHoc::config::<FavaConfig>())
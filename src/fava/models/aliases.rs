@@ -0,0 +1,115 @@
+use regex::Regex;
+use rocket::serde::{Deserialize, Serialize};
+
+use super::ledger::Transaction;
+use super::Statement;
+use crate::repository::Repository;
+
+/// Gives every statement whose label or counterparty matches `pattern` the same payee,
+/// `CARREFOUR MARKET GENT 1234` and `CARREFOUR EXPRESS` are both `Carrefour`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Alias {
+    pub id: String,
+    pub pattern: String,
+    pub payee: String,
+}
+
+pub type Aliases = Repository<Vec<Alias>>;
+
+impl Alias {
+    pub fn new(pattern: &str, payee: &str) -> Result<Self, regex::Error> {
+        Regex::new(pattern)?;
+
+        Ok(Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            pattern: pattern.to_string(),
+            payee: payee.to_string(),
+        })
+    }
+
+    /// Matches the payee as a word anywhere, in any case.
+    /// A word boundary next to `)` or `.` would need a word character after it, so it is left out.
+    fn for_payee(payee: &str) -> Self {
+        let is_word = |c: Option<char>| c.map(|c| c.is_alphanumeric() || c == '_').unwrap_or(false);
+        let start = if is_word(payee.chars().next()) {
+            r"\b"
+        } else {
+            ""
+        };
+        let end = if is_word(payee.chars().next_back()) {
+            r"\b"
+        } else {
+            ""
+        };
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            pattern: format!("(?i){}{}{}", start, regex::escape(payee), end),
+            payee: payee.to_string(),
+        }
+    }
+}
+
+/// Aliases for the payees of the ledger that no alias gives yet, longer payees come first so
+/// `Colruyt Gent` is tried before `Colruyt`. Very short payees would match too much.
+pub fn seed(aliases: &[Alias], ledger: &[Transaction]) -> Vec<Alias> {
+    let mut payees: Vec<&str> = ledger
+        .iter()
+        .filter_map(|x| x.payee.as_deref())
+        .map(str::trim)
+        .filter(|x| x.chars().filter(|c| c.is_alphanumeric()).count() >= 3)
+        .filter(|x| !aliases.iter().any(|a| a.payee == *x))
+        .collect();
+    payees.sort_unstable_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+    payees.dedup();
+
+    payees.into_iter().map(Alias::for_payee).collect()
+}
+
+/// Aliases with their regex compiled, the first one that matches wins
+pub struct Compiled<'a> {
+    aliases: Vec<(&'a Alias, Regex)>,
+}
+
+impl<'a> Compiled<'a> {
+    pub fn compile(aliases: &'a [Alias]) -> Self {
+        let aliases = aliases
+            .iter()
+            .filter_map(|x| Some((x, Regex::new(&x.pattern).ok()?)))
+            .collect();
+        Self { aliases }
+    }
+
+    pub fn payee(&self, statement: &Statement) -> Option<&'a str> {
+        let label = statement.description.as_ref().map(|x| x.label.as_str());
+        let texts: Vec<_> = [statement.tegenpartij.as_deref(), label]
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|x| !x.trim().is_empty())
+            .collect();
+
+        self.aliases
+            .iter()
+            .find(|(_, regex)| texts.iter().any(|x| regex.is_match(x)))
+            .map(|(alias, _)| alias.payee.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payee_pattern() {
+        let matches = |payee: &str, text: &str| {
+            Regex::new(&Alias::for_payee(payee).pattern)
+                .unwrap()
+                .is_match(text)
+        };
+        assert!(matches("Colruyt", "COLRUYT GENT"));
+        assert!(!matches("Colruyt", "COLRUYTGROUP"));
+        assert!(matches("Proximus N.V.", "PROXIMUS N.V. BRUSSEL"));
+        assert!(matches("Bakkerij (Gent)", "bakkerij (gent)"));
+        assert!(matches("A.S.Adventure", "A.S.ADVENTURE GENT"));
+    }
+}
@@ -0,0 +1,66 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+
+use super::money::Money;
+use super::own_accounts::OwnAccount;
+use super::{Scan, Statement};
+
+/// A beancount `balance` assertion, it checks the balance at the start of `date`
+pub struct Balance {
+    pub date: NaiveDate,
+    pub account: String,
+    pub amount: Money,
+}
+
+/// The balance after the last statement of a day.
+///
+/// Banks don't agree on the order of statements within a day, so the last one is the statement
+/// whose balance no other statement of that day starts from. Days where that is not exactly one
+/// statement get no assertion.
+fn day_end(statements: &[&Statement]) -> Option<isize> {
+    let ends: Vec<_> = statements
+        .iter()
+        .enumerate()
+        .filter_map(|(i, x)| Some((i, x.balance?)))
+        .filter(|(i, end)| {
+            !statements
+                .iter()
+                .enumerate()
+                .any(|(j, x)| j != *i && x.balance.map(|b| b - x.amount) == Some(*end))
+        })
+        .map(|(_, end)| end)
+        .collect();
+
+    match ends[..] {
+        [end] => Some(end),
+        _ => None,
+    }
+}
+
+impl Scan {
+    /// An assertion for the day after every day of every pay account the bank gave balances for.
+    /// Deleted statements count as well, they are on the bank's balance all the same.
+    pub fn balances(&self, pay: &str, own: &[OwnAccount]) -> Vec<Balance> {
+        let mut days: BTreeMap<_, Vec<&Statement>> = BTreeMap::new();
+        for statement in self.grouped.iter().flat_map(|x| x.statements.iter()) {
+            let account = statement.pay_account(own, pay);
+            days.entry((statement.date, account, statement.currency.as_str()))
+                .or_default()
+                .push(statement);
+        }
+
+        days.into_iter()
+            .filter_map(|((date, account, currency), statements)| {
+                Some(Balance {
+                    date: date.succ(),
+                    account: account.to_string(),
+                    amount: Money {
+                        cents: day_end(&statements)?,
+                        currency: currency.to_string(),
+                    },
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,434 @@
+use std::str::FromStr;
+
+use regex::Regex;
+use rocket::serde::{Deserialize, Serialize};
+
+use super::money::{self, Money};
+
+lazy_static! {
+    static ref DATE: Regex = Regex::new(r"\b[0-9]{2}[-/][0-9]{2}(?:[-/][0-9]{2,4})?\b").unwrap();
+    static ref CARD: Regex = Regex::new(r"\b[0-9]{4}(?: ?[0-9X*]{4}){2} ?[0-9]{4}\b").unwrap();
+    static ref TIME: Regex = Regex::new(r"\bOM ([0-9]{2}[.:][0-9]{2}) UUR\b").unwrap();
+    static ref SPACES: Regex = Regex::new(r"\s+").unwrap();
+    static ref FOREIGN: Regex =
+        Regex::new(r"\b([0-9]+(?:[.,][0-9]{3})*[.,][0-9]{2}) ([A-Z]{3})\b").unwrap();
+}
+
+/// What kind of transaction a statement is, with the parts of the description that matter for it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Kind {
+    /// Debit or credit card payment (Maestro, Visa, Mastercard)
+    Card {
+        card: Option<String>,
+        time: Option<String>,
+        merchant: String,
+    },
+    Bancontact {
+        card: Option<String>,
+        time: Option<String>,
+        merchant: String,
+    },
+    Instant {
+        counterparty: String,
+    },
+    Transfer {
+        counterparty: String,
+    },
+    StandingOrder {
+        counterparty: String,
+    },
+    DirectDebit {
+        creditor: String,
+        reference: Option<String>,
+    },
+    Withdrawal {
+        card: Option<String>,
+        place: Option<String>,
+    },
+    Refund {
+        counterparty: String,
+    },
+    Fee,
+    Interest,
+    #[default]
+    Other,
+}
+
+impl Kind {
+    /// Same as the serialized `type`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kind::Card { .. } => "card",
+            Kind::Bancontact { .. } => "bancontact",
+            Kind::Instant { .. } => "instant",
+            Kind::Transfer { .. } => "transfer",
+            Kind::StandingOrder { .. } => "standing_order",
+            Kind::DirectDebit { .. } => "direct_debit",
+            Kind::Withdrawal { .. } => "withdrawal",
+            Kind::Refund { .. } => "refund",
+            Kind::Fee => "fee",
+            Kind::Interest => "interest",
+            Kind::Other => "other",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Description {
+    pub(super) way: String,
+    pub(super) label: String,
+    pub(super) user: Option<String>,
+    #[serde(default)]
+    pub(super) kind: Kind,
+    /// What a card payment cost in the currency it was paid in
+    #[serde(default)]
+    pub(super) original: Option<Money>,
+    /// The description as the bank wrote it
+    #[serde(default)]
+    pub(super) text: String,
+}
+
+/// Text after the first occurrence of `marker`
+fn after<'a>(s: &'a str, marker: &str) -> Option<&'a str> {
+    s.split_once(marker)
+        .map(|(_, x)| x.trim())
+        .filter(|x| !x.is_empty())
+}
+
+/// The kinds of transaction that are recognised by a keyword
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Keyword {
+    Withdrawal,
+    Refund,
+    DirectDebit,
+    StandingOrder,
+    Instant,
+    Transfer,
+    Bancontact,
+    Card,
+}
+
+/// Keywords per kind, `true` when only a whole word counts.
+/// The others also match inside words, like `OVERSCHRIJVING` in `INSTANTOVERSCHRIJVING`.
+const KEYWORDS: &[(Keyword, &[(&str, bool)])] = &[
+    (
+        Keyword::Withdrawal,
+        &[
+            ("GELDOPNEMING", false),
+            ("GELDAFHALING", false),
+            ("CASH WITHDRAWAL", false),
+        ],
+    ),
+    (
+        Keyword::Refund,
+        &[
+            ("TERUGBETALING", false),
+            ("TERUGSTORTING", false),
+            ("CREDITNOTA", false),
+            ("REFUND", false),
+        ],
+    ),
+    (Keyword::DirectDebit, &[("DOMICILIERING", false)]),
+    (
+        Keyword::StandingOrder,
+        &[("DOORLOPENDE OPDRACHT", false), ("STANDING ORDER", false)],
+    ),
+    (Keyword::Instant, &[("INSTANT", false)]),
+    (
+        Keyword::Transfer,
+        &[("OVERSCHRIJVING", false), ("TRANSFER", true)],
+    ),
+    (Keyword::Bancontact, &[("BANCONTACT", false)]),
+    (
+        Keyword::Card,
+        &[
+            ("MAESTRO", false),
+            ("VISA", true),
+            ("MASTERCARD", false),
+            ("DEBETKAART", false),
+            ("KREDIETKAART", false),
+        ],
+    ),
+];
+
+/// Where `word` first occurs in `s`
+fn position(s: &str, word: &str, whole: bool) -> Option<usize> {
+    let is_boundary = |c: Option<char>| c.map(|c| !c.is_alphanumeric()).unwrap_or(true);
+    s.match_indices(word).map(|(i, _)| i).find(|&i| {
+        !whole
+            || (is_boundary(s[..i].chars().next_back())
+                && is_boundary(s[i + word.len()..].chars().next()))
+    })
+}
+
+/// The kind whose keyword comes first, the type of transaction leads the description.
+/// A direct debit that repays a loan mentions `TERUGBETALING` only after `DOMICILIERING`.
+fn keyword(s: &str) -> Option<Keyword> {
+    KEYWORDS
+        .iter()
+        .flat_map(|(keyword, words)| {
+            words
+                .iter()
+                .filter_map(move |(word, whole)| Some((position(s, word, *whole)?, *keyword)))
+        })
+        .min_by_key(|(at, _)| *at)
+        .map(|(_, keyword)| keyword)
+}
+
+/// Whole words only, these are too short to look for anywhere in the text
+fn has_word(s: &str, matches: impl Fn(&str) -> bool) -> bool {
+    s.split(|c: char| !c.is_alphanumeric()).any(matches)
+}
+
+/// The first amount with a known currency, card payments abroad mention what was paid
+fn original(s: &str) -> Option<Money> {
+    FOREIGN
+        .captures_iter(s)
+        .filter(|x| money::CURRENCIES.contains(&&x[2]))
+        .find_map(|x| {
+            Some(Money {
+                cents: money::parse_cents(&x[1]).ok()?,
+                currency: x[2].to_string(),
+            })
+        })
+}
+
+/// Card payments and withdrawals look like `... 1234 XXXX XXXX 5678 03-01 OM 17.45 UUR COLRUYT GENT MET KBC-DEBETKAART VAN: JAN`
+struct CardText {
+    card: Option<String>,
+    time: Option<String>,
+    place: Option<String>,
+    user: Option<String>,
+}
+
+impl CardText {
+    fn parse(s: &str, rest: &str) -> Self {
+        let card = CARD.find(s).map(|x| x.as_str().to_string());
+        let time = TIME.captures(s).map(|x| x[1].to_string());
+
+        let place = after(s, " UUR ").unwrap_or(rest);
+        let (place, user) = match place.split_once(" MET ") {
+            Some((place, user)) => (place, after(user, ":")),
+            None => (place, None),
+        };
+        // Payments abroad put the original amount and exchange rate after the merchant
+        let place = FOREIGN
+            .captures_iter(place)
+            .find(|x| money::CURRENCIES.contains(&&x[2]))
+            .map(|x| &place[..x.get(0).unwrap().start()])
+            .unwrap_or(place)
+            .trim();
+
+        Self {
+            card,
+            time,
+            place: Some(place.to_string()).filter(|x| !x.is_empty()),
+            user: user.map(String::from),
+        }
+    }
+}
+
+impl FromStr for Description {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = SPACES.replace_all(s.trim(), " ");
+        if s.is_empty() {
+            return Err("Empty description");
+        }
+        let upper = s.to_uppercase();
+
+        // Most banks put the (short) date right after the kind of transaction
+        let (way, rest) = match DATE.find(&s) {
+            Some(m) => (s[..m.start()].trim(), s[m.end()..].trim()),
+            None => match s.split_once(':') {
+                Some((way, rest)) => (way.trim(), rest.trim()),
+                None => (s.as_ref(), ""),
+            },
+        };
+        let counterparty = || {
+            after(rest, ":")
+                .or_else(|| Some(rest).filter(|x| !x.is_empty()))
+                .unwrap_or(&s)
+                .to_string()
+        };
+
+        let mut user = None;
+        // The part before the date names the type of transaction, the rest can mention anything
+        let found = keyword(&way.to_uppercase()).or_else(|| keyword(&upper));
+        let kind = match found {
+            Some(Keyword::Withdrawal) => {
+                let card = CardText::parse(&s, rest);
+                user = card.user;
+                Kind::Withdrawal {
+                    card: card.card,
+                    place: card.place,
+                }
+            }
+            Some(Keyword::Refund) => Kind::Refund {
+                counterparty: counterparty(),
+            },
+            Some(Keyword::DirectDebit) => {
+                let creditor = after(rest, ":").unwrap_or(rest);
+                let (creditor, reference) = match creditor.split_once(" REF.") {
+                    Some((creditor, reference)) => (creditor, Some(reference.trim().to_string())),
+                    None => (creditor, None),
+                };
+                Kind::DirectDebit {
+                    creditor: creditor.trim().to_string(),
+                    reference: reference.filter(|x| !x.is_empty()),
+                }
+            }
+            Some(Keyword::StandingOrder) => Kind::StandingOrder {
+                counterparty: counterparty(),
+            },
+            Some(Keyword::Instant) => Kind::Instant {
+                counterparty: counterparty(),
+            },
+            Some(Keyword::Transfer) => Kind::Transfer {
+                counterparty: counterparty(),
+            },
+            Some(Keyword::Bancontact) => {
+                let card = CardText::parse(&s, rest);
+                user = card.user;
+                Kind::Bancontact {
+                    card: card.card,
+                    time: card.time,
+                    merchant: card.place.unwrap_or_default(),
+                }
+            }
+            Some(Keyword::Card) => {
+                let card = CardText::parse(&s, rest);
+                user = card.user;
+                Kind::Card {
+                    card: card.card,
+                    time: card.time,
+                    merchant: card.place.unwrap_or_default(),
+                }
+            }
+            None if has_word(&upper, |x| {
+                x.ends_with("KOSTEN") || matches!(x, "BIJDRAGE" | "AANREKENING" | "FEE" | "FEES")
+            }) =>
+            {
+                Kind::Fee
+            }
+            None if has_word(&upper, |x| {
+                x.ends_with("RENTE") || x.starts_with("INTEREST") || x == "GETROUWHEIDSPREMIE"
+            }) =>
+            {
+                Kind::Interest
+            }
+            None => Kind::Other,
+        };
+
+        let label = match &kind {
+            Kind::Card { merchant, .. } | Kind::Bancontact { merchant, .. } => merchant.clone(),
+            Kind::Instant { counterparty }
+            | Kind::Transfer { counterparty }
+            | Kind::StandingOrder { counterparty }
+            | Kind::Refund { counterparty } => counterparty.clone(),
+            Kind::DirectDebit { creditor, .. } => creditor.clone(),
+            Kind::Withdrawal { place, .. } => place.clone().unwrap_or_else(|| way.to_string()),
+            Kind::Fee | Kind::Interest | Kind::Other => {
+                if rest.is_empty() { &s } else { rest }.to_string()
+            }
+        };
+
+        let original = match kind {
+            Kind::Card { .. } | Kind::Bancontact { .. } | Kind::Withdrawal { .. } => original(&s),
+            _ => None,
+        };
+
+        Ok(Description {
+            way: way.to_string(),
+            label,
+            user,
+            kind,
+            original,
+            text: s.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `<kind>|<label>|<description>` per line, `#` starts a comment
+    const CORPUS: &str = include_str!("../../../tests/fixtures/descriptions.txt");
+
+    #[test]
+    fn corpus() {
+        for line in CORPUS.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.splitn(3, '|');
+            let (kind, label, text) = (
+                parts.next().unwrap(),
+                parts.next().unwrap(),
+                parts.next().unwrap(),
+            );
+
+            let description: Description = text.parse().unwrap();
+            assert_eq!(description.kind.name(), kind, "kind of '{}'", text);
+            assert_eq!(description.label, label, "label of '{}'", text);
+        }
+    }
+
+    #[test]
+    fn card_fields() {
+        let description: Description = "BETALING AANKOPEN VIA BANCONTACT 1234 XXXX XXXX 5678 03-01 OM 17.45 UUR COLRUYT GENT MET KBC-DEBETKAART VAN: JAN PEETERS"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            description.kind,
+            Kind::Bancontact {
+                card: Some("1234 XXXX XXXX 5678".to_string()),
+                time: Some("17.45".to_string()),
+                merchant: "COLRUYT GENT".to_string(),
+            }
+        );
+        assert_eq!(description.user.as_deref(), Some("JAN PEETERS"));
+        assert_eq!(
+            description.way,
+            "BETALING AANKOPEN VIA BANCONTACT 1234 XXXX XXXX 5678"
+        );
+    }
+
+    #[test]
+    fn direct_debit_reference() {
+        let description: Description =
+            "EUROPESE DOMICILIERING 04-01 SCHULDEISER : PROXIMUS REF. 123"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            description.kind,
+            Kind::DirectDebit {
+                creditor: "PROXIMUS".to_string(),
+                reference: Some("123".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn foreign_card_payment() {
+        let description: Description = "BETALING AANKOPEN VIA MAESTRO 1234 XXXX XXXX 5678 10-03 OM 12.00 UUR AMAZON US 10,00 USD WISSELKOERS 1,0870 KOST 0,17 EUR MET KBC-DEBETKAART VAN: JAN PEETERS"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            description.original,
+            Some(Money {
+                cents: 1000,
+                currency: "USD".to_string(),
+            })
+        );
+        assert_eq!(description.label, "AMAZON US");
+    }
+
+    #[test]
+    fn empty() {
+        assert!("   ".parse::<Description>().is_err());
+    }
+}
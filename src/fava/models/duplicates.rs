@@ -0,0 +1,188 @@
+use super::ledger::Transaction;
+use super::{GroupedStatement, Scan, Statement};
+
+fn normalise(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn digits(s: &str) -> String {
+    s.chars().filter(char::is_ascii_digit).collect()
+}
+
+impl Statement {
+    /// Who the money went to or came from, the card payment label when there is no counterparty
+    fn counterparty_key(&self) -> Option<String> {
+        self.tegenpartij
+            .as_deref()
+            .or_else(|| self.description.as_ref().map(|x| x.label.as_str()))
+            .map(normalise)
+            .filter(|x| !x.is_empty())
+    }
+
+    fn structured_key(&self) -> Option<String> {
+        self.note.structured.as_deref().map(digits)
+    }
+
+    /// Same date, amount, counterparty and structured communication
+    pub fn is_duplicate_of(&self, other: &Statement) -> bool {
+        self.date == other.date
+            && self.amount == other.amount
+            && self.currency == other.currency
+            && self.counterparty_key() == other.counterparty_key()
+            && self.structured_key() == other.structured_key()
+    }
+
+    /// The ledger knows the date, the amounts, the name that was written as narration and the
+    /// metadata. The import id and the structured communication decide before the name does.
+    pub fn matches_transaction(&self, tx: &Transaction) -> bool {
+        if tx.meta.get("import-id") == Some(&self.id.0) {
+            return true;
+        }
+
+        let same_amount = tx.postings.iter().any(|x| {
+            x.amount == Some(self.amount)
+                && x.currency
+                    .as_deref()
+                    .map(|c| c == self.currency)
+                    .unwrap_or(true)
+        });
+        if self.date != tx.date || !same_amount {
+            return false;
+        }
+
+        let ogm = tx.meta.get("ogm").map(|x| digits(x));
+        if let (Some(ours), Some(theirs)) = (self.structured_key(), ogm) {
+            return ours == theirs;
+        }
+
+        let key = match self.counterparty_key() {
+            Some(x) => x,
+            None => return true,
+        };
+        let narration = normalise(&tx.narration);
+        let payee = tx.payee.as_deref().map(normalise).unwrap_or_default();
+        narration.contains(&key) || (!payee.is_empty() && key.contains(&payee))
+    }
+}
+
+impl Scan {
+    fn active_statements(&self) -> impl Iterator<Item = &Statement> {
+        self.grouped
+            .iter()
+            .filter(|x| x.key != "deleted")
+            .flat_map(|x| x.statements.iter())
+    }
+
+    /// Flags statements that are already part of another scan or of the ledger
+    pub fn flag_duplicates(&mut self, others: &[Scan], ledger: &[Transaction]) {
+        for group in self.grouped.iter_mut() {
+            for statement in group.statements.iter_mut() {
+                let in_scan = others.iter().find(|scan| {
+                    scan.active_statements()
+                        .any(|x| statement.is_duplicate_of(x))
+                });
+                if let Some(scan) = in_scan {
+                    statement.duplicate = Some(format!("Also in scan {}", scan.id));
+                    continue;
+                }
+
+                if let Some(tx) = ledger.iter().find(|tx| statement.matches_transaction(tx)) {
+                    statement.duplicate = Some(format!(
+                        "Already in the ledger: {} {}",
+                        tx.date.format("%Y-%m-%d"),
+                        tx.payee.as_deref().unwrap_or(&tx.narration)
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Suspected duplicates that still have to be reviewed, with their group
+    pub fn duplicates(&self) -> impl Iterator<Item = (&GroupedStatement, &Statement)> {
+        self.grouped
+            .iter()
+            .filter(|x| x.key != "deleted")
+            .flat_map(|g| g.statements.iter().map(move |x| (g, x)))
+            .filter(|(_, x)| x.duplicate.is_some())
+    }
+
+    /// Marks a suspected duplicate as a genuine statement
+    pub fn keep(&mut self, group_id: &str, item_id: &str) {
+        if let Some(group) = self.grouped.iter_mut().find(|x| x.key == group_id) {
+            if let Some(x) = group.statements.iter_mut().find(|x| x.id.0 == item_id) {
+                x.duplicate = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::super::ledger;
+    use super::super::{GroupingStrategy, Note};
+    use super::*;
+
+    fn statement(day: u32, cents: isize, counterparty: &str, ogm: Option<&str>) -> Statement {
+        let mut statement = Statement::new(NaiveDate::from_ymd_opt(2023, 1, day).unwrap(), cents);
+        statement.tegenpartij = Some(counterparty.to_string());
+        statement.note = Note {
+            structured: ogm.map(String::from),
+            free: None,
+        };
+        statement
+    }
+
+    #[test]
+    fn match_ledger() {
+        let ledger = ledger::parse(
+            "2023-01-03 * \"COLRUYT GENT\"\n    Assets:Bank -12.50 EUR\n    Expenses:Food\n\n\
+             2023-01-04 * \"Water\" \"invoice\"\n    ogm: \"+++090/9337/55493+++\"\n    Assets:Bank -40.00 EUR\n    Expenses:Water\n",
+        );
+
+        assert!(statement(3, -1250, "Colruyt Gent", None).matches_transaction(&ledger[0]));
+        assert!(!statement(3, -1251, "Colruyt Gent", None).matches_transaction(&ledger[0]));
+        assert!(!statement(4, -1250, "Colruyt Gent", None).matches_transaction(&ledger[0]));
+        assert!(!statement(3, -1250, "Delhaize", None).matches_transaction(&ledger[0]));
+
+        // The structured communication decides, the name doesn't matter
+        let same = statement(4, -4000, "Farys", Some("090933755493"));
+        assert!(same.matches_transaction(&ledger[1]));
+        let other = statement(4, -4000, "Water", Some("020343212348"));
+        assert!(!other.matches_transaction(&ledger[1]));
+
+        // A posted statement is recognised by its import id, even when it was edited
+        let posted = statement(9, -1, "Someone else", None);
+        let with_id = format!(
+            "2023-01-03 * \"Shop\"\n    import-id: \"{}\"\n    Assets:Bank -12.50 EUR\n    Expenses:Food\n",
+            posted.id.0
+        );
+        assert!(posted.matches_transaction(&ledger::parse(&with_id)[0]));
+    }
+
+    #[test]
+    fn flag_and_keep() {
+        let first = statement(3, -4000, "Water", Some("+++090/9337/55493+++"));
+        let twin = statement(3, -4000, "Water", Some("090933755493"));
+        let other = statement(3, -4000, "Water", Some("020343212348"));
+        let earlier = Scan::new(vec![first], &[], &[], GroupingStrategy::default());
+
+        let mut scan = Scan::new(vec![twin, other], &[], &[], GroupingStrategy::default());
+        scan.flag_duplicates(std::slice::from_ref(&earlier), &[]);
+        assert!(!scan.is_ready());
+        let flagged: Vec<_> = scan.duplicates().collect();
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(
+            flagged[0].1.duplicate,
+            Some(format!("Also in scan {}", earlier.id))
+        );
+
+        let (group, item) = (flagged[0].0.key.clone(), flagged[0].1.id.0.clone());
+        scan.keep(&group, &item);
+        assert_eq!(scan.duplicates().count(), 0);
+    }
+}
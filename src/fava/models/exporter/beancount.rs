@@ -0,0 +1,35 @@
+use super::{Balance, Exporter, ScanOutput};
+
+pub struct Beancount;
+
+impl Exporter for Beancount {
+    fn name(&self) -> &'static str {
+        "beancount"
+    }
+
+    fn label(&self) -> &'static str {
+        "Beancount"
+    }
+
+    fn extension(&self) -> &'static str {
+        "beancount"
+    }
+
+    fn write(&self, outputs: &[ScanOutput]) -> String {
+        outputs.iter().map(|x| format!("\n{}\n", x)).collect()
+    }
+
+    fn balances(&self, balances: &[Balance]) -> String {
+        balances
+            .iter()
+            .map(|x| {
+                format!(
+                    "\n{} balance {} {}\n",
+                    x.date.format("%Y-%m-%d"),
+                    x.account,
+                    x.amount
+                )
+            })
+            .collect()
+    }
+}
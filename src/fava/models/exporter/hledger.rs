@@ -0,0 +1,64 @@
+use std::fmt::Write;
+
+use super::{description, Exporter, ScanOutput};
+
+/// hledger journal, tags, links and metadata all become hledger tags
+pub struct Hledger;
+
+impl Exporter for Hledger {
+    fn name(&self) -> &'static str {
+        "hledger"
+    }
+
+    fn label(&self) -> &'static str {
+        "hledger"
+    }
+
+    fn extension(&self) -> &'static str {
+        "journal"
+    }
+
+    fn write(&self, outputs: &[ScanOutput]) -> String {
+        let mut out = String::new();
+        for output in outputs {
+            let _ = write_one(&mut out, output);
+        }
+        out
+    }
+}
+
+fn write_one(out: &mut String, output: &ScanOutput) -> std::fmt::Result {
+    write!(out, "\n{} *", output.date.format("%Y-%m-%d"))?;
+    if let Some(payee) = &output.payee {
+        write!(out, " {} |", description(payee))?;
+    }
+    write!(out, " {}", description(&output.narration))?;
+
+    let tags: Vec<_> = output
+        .tags
+        .iter()
+        .map(|x| format!("{}:", x))
+        .chain(output.links.iter().map(|x| format!("link:{}", x)))
+        .collect();
+    if !tags.is_empty() {
+        write!(out, "  ; {}", tags.join(", "))?;
+    }
+    writeln!(out)?;
+
+    for (key, value) in output.metadata.iter() {
+        writeln!(out, "    ; {}: {}", key, value.replace(',', " "))?;
+    }
+
+    for posting in output.postings() {
+        match (posting.elided, posting.price) {
+            (true, _) => writeln!(out, "    {}", posting.account)?,
+            (false, Some(cost)) => writeln!(
+                out,
+                "    {}  {} @@ {}",
+                posting.account, posting.amount, cost
+            )?,
+            (false, None) => writeln!(out, "    {}  {}", posting.account, posting.amount)?,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use rocket::serde::json::serde_json::{self, json, Map, Value};
+
+use super::super::money::format_cents;
+use super::{Exporter, ScanOutput};
+
+/// Amounts are strings like `-12.50`, so they stay exact
+pub struct Json;
+
+impl Exporter for Json {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn label(&self) -> &'static str {
+        "JSON"
+    }
+
+    fn extension(&self) -> &'static str {
+        "json"
+    }
+
+    fn write(&self, outputs: &[ScanOutput]) -> String {
+        let transactions: Vec<_> = outputs
+            .iter()
+            .map(|output| {
+                let metadata: Map<String, Value> = output
+                    .metadata
+                    .iter()
+                    .map(|(key, value)| (key.clone(), Value::from(value.as_str())))
+                    .collect();
+                let postings: Vec<_> = output
+                    .postings()
+                    .iter()
+                    .map(|posting| {
+                        json!({
+                            "account": posting.account,
+                            "amount": format_cents(posting.amount.cents),
+                            "currency": posting.amount.currency,
+                            "price": posting.price.map(|x| json!({
+                                "amount": format_cents(x.cents),
+                                "currency": x.currency,
+                            })),
+                        })
+                    })
+                    .collect();
+
+                json!({
+                    "date": output.date.format("%Y-%m-%d").to_string(),
+                    "payee": output.payee,
+                    "narration": output.narration,
+                    "metadata": metadata,
+                    "tags": output.tags,
+                    "links": output.links,
+                    "postings": postings,
+                })
+            })
+            .collect();
+
+        serde_json::to_string_pretty(&transactions).unwrap_or_default()
+    }
+}
@@ -0,0 +1,63 @@
+use std::fmt::Write;
+
+use super::{description, Exporter, ScanOutput};
+
+/// ledger-cli journal, links become tags
+pub struct LedgerCli;
+
+impl Exporter for LedgerCli {
+    fn name(&self) -> &'static str {
+        "ledger"
+    }
+
+    fn label(&self) -> &'static str {
+        "Ledger"
+    }
+
+    fn extension(&self) -> &'static str {
+        "ledger"
+    }
+
+    fn write(&self, outputs: &[ScanOutput]) -> String {
+        let mut out = String::new();
+        for output in outputs {
+            let _ = write_one(&mut out, output);
+        }
+        out
+    }
+}
+
+fn write_one(out: &mut String, output: &ScanOutput) -> std::fmt::Result {
+    let payee = output.payee.as_ref().unwrap_or(&output.narration);
+    writeln!(
+        out,
+        "\n{} * {}",
+        output.date.format("%Y/%m/%d"),
+        description(payee)
+    )?;
+    if output.payee.is_some() {
+        writeln!(out, "    ; {}", output.narration)?;
+    }
+
+    let tags: Vec<_> = output.tags.iter().chain(output.links.iter()).collect();
+    if !tags.is_empty() {
+        let tags: String = tags.iter().map(|x| format!("{}:", x)).collect();
+        writeln!(out, "    ; :{}", tags)?;
+    }
+    for (key, value) in output.metadata.iter() {
+        writeln!(out, "    ; {}: {}", key, value)?;
+    }
+
+    for posting in output.postings() {
+        match (posting.elided, posting.price) {
+            (true, _) => writeln!(out, "    {}", posting.account)?,
+            (false, Some(cost)) => writeln!(
+                out,
+                "    {}  {} @@ {}",
+                posting.account, posting.amount, cost
+            )?,
+            (false, None) => writeln!(out, "    {}  {}", posting.account, posting.amount)?,
+        }
+    }
+    Ok(())
+}
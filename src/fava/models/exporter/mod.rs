@@ -0,0 +1,227 @@
+use super::balance::Balance;
+use super::money::Money;
+use super::ScanOutput;
+
+mod beancount;
+mod hledger;
+mod json;
+mod ledger_cli;
+mod table;
+
+pub use beancount::Beancount;
+pub use hledger::Hledger;
+pub use json::Json;
+pub use ledger_cli::LedgerCli;
+pub use table::Csv;
+
+/// Writes the transactions of a categorised scan in the format of one tool
+pub trait Exporter: Sync {
+    /// Short name used in urls, e.g. `hledger`
+    fn name(&self) -> &'static str;
+
+    /// Name shown on the download button
+    fn label(&self) -> &'static str;
+
+    fn extension(&self) -> &'static str;
+
+    fn write(&self, outputs: &[ScanOutput]) -> String;
+
+    /// Balance assertions, formats without them leave them out
+    fn balances(&self, _balances: &[Balance]) -> String {
+        String::new()
+    }
+}
+
+pub const EXPORTERS: &[&dyn Exporter] = &[&Beancount, &LedgerCli, &Hledger, &Csv, &Json];
+
+pub fn by_name(name: &str) -> Option<&'static dyn Exporter> {
+    EXPORTERS
+        .iter()
+        .find(|x| x.name().eq_ignore_ascii_case(name))
+        .copied()
+}
+
+/// A posting with its amount filled in, `elided` marks the amount beancount would infer
+pub struct Posting<'a> {
+    pub account: &'a str,
+    pub amount: Money,
+    pub price: Option<&'a Money>,
+    pub elided: bool,
+}
+
+impl ScanOutput<'_, '_> {
+    pub fn postings(&self) -> Vec<Posting<'_>> {
+        let mut postings = vec![Posting {
+            account: self.pay,
+            amount: self.amount.clone(),
+            price: None,
+            elided: false,
+        }];
+
+        postings.extend(self.splits.iter().map(|(account, amount)| Posting {
+            account,
+            amount: amount.clone(),
+            price: None,
+            elided: false,
+        }));
+
+        if let Some(category) = &self.category {
+            postings.push(match &self.price {
+                Some((original, cost)) => Posting {
+                    account: category,
+                    amount: original.clone(),
+                    price: Some(cost),
+                    elided: false,
+                },
+                None => Posting {
+                    account: category,
+                    amount: Money {
+                        cents: -postings.iter().map(|x| x.amount.cents).sum::<isize>(),
+                        currency: self.amount.currency.clone(),
+                    },
+                    price: None,
+                    elided: true,
+                },
+            });
+        }
+
+        postings
+    }
+}
+
+/// Descriptions of ledger and hledger end at a comment
+fn description(s: &str) -> String {
+    s.replace(';', ",")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use rocket::serde::json::serde_json::{self, Value};
+
+    use super::super::output::OutputConfig;
+    use super::super::split::Share;
+    use super::super::{GroupingStrategy, Note, Scan, Statement};
+    use super::*;
+
+    fn statement(day: u32, cents: isize, counterparty: &str, note: &str) -> Statement {
+        let mut statement = Statement::new(NaiveDate::from_ymd_opt(2023, 1, day).unwrap(), cents);
+        statement.tegenpartij = Some(counterparty.to_string());
+        statement.note = Note {
+            structured: None,
+            free: Some(note.to_string()),
+        };
+        statement
+    }
+
+    /// Groceries with a balance and a phone bill that is partly paid for work
+    fn export(exporter: &dyn Exporter) -> String {
+        let mut food = statement(3, -1250, "Colruyt; Gent", "groceries");
+        food.balance = Some(100000);
+        let mut phone = statement(4, -4000, "Proximus", "internet");
+        phone
+            .add_split("Expenses:Work", Share::Percent(25.0))
+            .unwrap();
+
+        let mut scan = Scan::new(vec![food, phone], &[], &[], GroupingStrategy::Counterparty);
+        scan.categorise("COLRUYT; GENT", "Expenses:Food");
+        scan.categorise("PROXIMUS", "Expenses:Phone");
+
+        let config = OutputConfig {
+            narration: "{free}".to_string(),
+            metadata: vec!["counterparty".to_string()],
+            tags: vec!["bank".to_string()],
+            ..OutputConfig::default()
+        };
+        scan.export(exporter, "Assets:Bank", &[], &config)
+    }
+
+    #[test]
+    fn beancount() {
+        assert_eq!(
+            export(&Beancount),
+            "
+2023-01-03 * \"Colruyt; Gent\" \"groceries\" #bank
+    counterparty: \"Colruyt; Gent\"
+    Assets:Bank -12.50 EUR
+    Expenses:Food
+
+
+2023-01-04 * \"Proximus\" \"internet\" #bank
+    counterparty: \"Proximus\"
+    Assets:Bank -40.00 EUR
+    Expenses:Work 10.00 EUR
+    Expenses:Phone
+
+
+2023-01-04 balance Assets:Bank 1000.00 EUR
+"
+        );
+    }
+
+    #[test]
+    fn ledger_and_hledger() {
+        assert_eq!(
+            export(&LedgerCli),
+            "
+2023/01/03 * Colruyt, Gent
+    ; groceries
+    ; :bank:
+    ; counterparty: Colruyt; Gent
+    Assets:Bank  -12.50 EUR
+    Expenses:Food
+
+2023/01/04 * Proximus
+    ; internet
+    ; :bank:
+    ; counterparty: Proximus
+    Assets:Bank  -40.00 EUR
+    Expenses:Work  10.00 EUR
+    Expenses:Phone
+"
+        );
+        assert_eq!(
+            export(&Hledger),
+            "
+2023-01-03 * Colruyt, Gent | groceries  ; bank:
+    ; counterparty: Colruyt; Gent
+    Assets:Bank  -12.50 EUR
+    Expenses:Food
+
+2023-01-04 * Proximus | internet  ; bank:
+    ; counterparty: Proximus
+    Assets:Bank  -40.00 EUR
+    Expenses:Work  10.00 EUR
+    Expenses:Phone
+"
+        );
+    }
+
+    #[test]
+    fn csv_and_json() {
+        assert_eq!(
+            export(&Csv),
+            "\
+date,payee,narration,account,amount,currency,price,price_currency,tags,links,counterparty
+2023-01-03,Colruyt; Gent,groceries,Assets:Bank,-12.50,EUR,,,bank,,Colruyt; Gent
+2023-01-03,Colruyt; Gent,groceries,Expenses:Food,12.50,EUR,,,bank,,Colruyt; Gent
+2023-01-04,Proximus,internet,Assets:Bank,-40.00,EUR,,,bank,,Proximus
+2023-01-04,Proximus,internet,Expenses:Work,10.00,EUR,,,bank,,Proximus
+2023-01-04,Proximus,internet,Expenses:Phone,30.00,EUR,,,bank,,Proximus
+"
+        );
+
+        let json: Value = serde_json::from_str(&export(&Json)).unwrap();
+        assert_eq!(json.as_array().map(Vec::len), Some(2));
+        assert_eq!(json[1]["payee"], "Proximus");
+        assert_eq!(json[1]["metadata"]["counterparty"], "Proximus");
+        assert_eq!(json[1]["postings"][2]["account"], "Expenses:Phone");
+        assert_eq!(json[1]["postings"][2]["amount"], "30.00");
+    }
+
+    #[test]
+    fn exporter_by_name() {
+        assert_eq!(by_name("HLEDGER").map(|x| x.extension()), Some("journal"));
+        assert!(by_name("qif").is_none());
+    }
+}
@@ -0,0 +1,84 @@
+use super::super::money::format_cents;
+use super::{Exporter, ScanOutput};
+
+/// One row per posting, every metadata key gets a column
+pub struct Csv;
+
+const COLUMNS: &[&str] = &[
+    "date",
+    "payee",
+    "narration",
+    "account",
+    "amount",
+    "currency",
+    "price",
+    "price_currency",
+    "tags",
+    "links",
+];
+
+impl Exporter for Csv {
+    fn name(&self) -> &'static str {
+        "csv"
+    }
+
+    fn label(&self) -> &'static str {
+        "CSV"
+    }
+
+    fn extension(&self) -> &'static str {
+        "csv"
+    }
+
+    fn write(&self, outputs: &[ScanOutput]) -> String {
+        let mut keys: Vec<&str> = Vec::new();
+        for (key, _) in outputs.iter().flat_map(|x| x.metadata.iter()) {
+            if !keys.contains(&key.as_str()) {
+                keys.push(key);
+            }
+        }
+
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        let _ = writer.write_record(COLUMNS.iter().chain(keys.iter()));
+
+        for output in outputs {
+            let date = output.date.format("%Y-%m-%d").to_string();
+            let meta = keys.iter().map(|key| {
+                output
+                    .metadata
+                    .iter()
+                    .find(|(x, _)| x == key)
+                    .map(|(_, x)| x.as_str())
+                    .unwrap_or("")
+            });
+            let meta: Vec<_> = meta.collect();
+
+            for posting in output.postings() {
+                let amount = format_cents(posting.amount.cents);
+                let (price, price_currency) = posting
+                    .price
+                    .map(|x| (format_cents(x.cents), x.currency.as_str()))
+                    .unwrap_or_default();
+                let row = [
+                    date.as_str(),
+                    output.payee.as_deref().unwrap_or(""),
+                    &output.narration,
+                    posting.account,
+                    &amount,
+                    &posting.amount.currency,
+                    &price,
+                    price_currency,
+                    &output.tags.join(" "),
+                    &output.links.join(" "),
+                ];
+                let _ = writer.write_record(row.iter().chain(meta.iter()));
+            }
+        }
+
+        writer
+            .into_inner()
+            .ok()
+            .and_then(|x| String::from_utf8(x).ok())
+            .unwrap_or_default()
+    }
+}
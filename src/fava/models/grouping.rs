@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+
+use rocket::serde::{Deserialize, Serialize};
+
+use super::{iban, GroupedStatement, Scan, Statement};
+
+/// Words that don't tell payees apart
+const NOISE: &[&str] = &["NV", "BV", "SA", "SPRL", "BVBA", "VZW", "REF"];
+
+/// How the statements of a new scan are put into groups
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupingStrategy {
+    /// `"{label} {tegenpartij}"`
+    #[default]
+    LabelCounterparty,
+    /// The counterparty, the label when there is none
+    Counterparty,
+    /// The payee without references, numbers and legal forms
+    NormalisedPayee,
+    /// The IBAN of the counterparty, names change but the account stays the same.
+    /// Statements without a valid IBAN are grouped by counterparty.
+    Iban,
+}
+
+impl GroupingStrategy {
+    pub const ALL: &'static [(&'static str, GroupingStrategy)] = &[
+        ("label_counterparty", GroupingStrategy::LabelCounterparty),
+        ("counterparty", GroupingStrategy::Counterparty),
+        ("normalised_payee", GroupingStrategy::NormalisedPayee),
+        ("iban", GroupingStrategy::Iban),
+    ];
+
+    pub fn by_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|(x, _)| *x == name).map(|(_, x)| *x)
+    }
+
+    pub fn key(self, statement: &Statement) -> String {
+        let label = statement
+            .description
+            .as_ref()
+            .map(|x| x.label.as_str())
+            .unwrap_or("");
+        let tegenpartij = statement.tegenpartij.as_deref().unwrap_or("");
+        let payee = if tegenpartij.trim().is_empty() {
+            label
+        } else {
+            tegenpartij
+        };
+
+        // An alias gives all spellings of a payee one group, the IBAN is more precise still
+        if let (Some(alias), false) = (&statement.payee, self == GroupingStrategy::Iban) {
+            return alias.clone();
+        }
+
+        match self {
+            GroupingStrategy::LabelCounterparty => format!("{} {}", label, tegenpartij),
+            GroupingStrategy::Counterparty => payee.trim().to_uppercase(),
+            GroupingStrategy::NormalisedPayee => {
+                let words: Vec<_> = payee
+                    .to_uppercase()
+                    .split(|c: char| !c.is_alphabetic())
+                    .filter(|x| x.len() > 1 && !NOISE.contains(x))
+                    .map(String::from)
+                    .collect();
+                words.join(" ")
+            }
+            GroupingStrategy::Iban => match statement.iban() {
+                Some(iban) => iban::format(&iban),
+                None => GroupingStrategy::Counterparty.key(statement),
+            },
+        }
+    }
+
+    /// Groups sorted by total, the trailing "deleted" group is not included
+    pub fn group(self, items: Vec<Statement>) -> Vec<GroupedStatement> {
+        let mut grouped: HashMap<String, GroupedStatement> = HashMap::new();
+
+        for item in items {
+            let key = self.key(&item);
+            grouped
+                .entry(key.clone())
+                .or_insert_with(|| GroupedStatement::new(key))
+                .statements
+                .push(item);
+        }
+
+        let mut grouped: Vec<_> = grouped.into_values().collect();
+        for group in grouped.iter_mut() {
+            group.sort();
+            // A group is only categorised when all its statements agree
+            let first = group.statements.first().and_then(|x| x.category.clone());
+            if group.statements.iter().all(|x| x.category == first) {
+                group.category = first;
+            }
+        }
+        grouped.sort_by_key(|x| x.total());
+
+        grouped
+    }
+}
+
+impl GroupedStatement {
+    /// Statements follow the category of the group they are in
+    pub(super) fn inherit_category(&mut self) {
+        let category = self.category.clone();
+        for statement in self.statements.iter_mut() {
+            statement.category = category.clone();
+        }
+    }
+}
+
+impl Scan {
+    pub(super) fn group_index(&self, key: &str) -> Option<usize> {
+        self.grouped.iter().position(|x| x.key == key)
+    }
+
+    /// The "deleted" group always stays last
+    pub(super) fn insert_group(&mut self, group: GroupedStatement) -> usize {
+        let index = self.group_index("deleted").unwrap_or(self.grouped.len());
+        self.grouped.insert(index, group);
+        index
+    }
+
+    /// Moves all statements of `from` into `into`, `from` disappears
+    pub fn merge(&mut self, from: &str, into: &str) {
+        if from == into || from == "deleted" || into == "deleted" {
+            return;
+        }
+        if self.group_index(into).is_none() {
+            return;
+        }
+        let from = match self.group_index(from) {
+            Some(index) => self.grouped.remove(index),
+            None => return,
+        };
+
+        if let Some(index) = self.group_index(into) {
+            let group = &mut self.grouped[index];
+            group.statements.extend(from.statements);
+            group.category = group.category.take().or(from.category);
+            group.inherit_category();
+            group.sort();
+        }
+    }
+
+    /// Moves a statement to another group, an unknown `to` starts a new group
+    pub fn move_item(&mut self, from: &str, item_id: &str, to: &str) {
+        let to = to.trim();
+        if from == to || to.is_empty() || from == "deleted" || to == "deleted" {
+            return;
+        }
+
+        let statement = match self.group_index(from) {
+            Some(index) => {
+                let group = &mut self.grouped[index];
+                let position = group.statements.iter().position(|x| x.id.0 == item_id);
+                let statement = position.map(|x| group.statements.remove(x));
+                if group.statements.is_empty() {
+                    self.grouped.remove(index);
+                }
+                statement
+            }
+            None => None,
+        };
+        let statement = match statement {
+            Some(x) => x,
+            None => return,
+        };
+
+        let index = self
+            .group_index(to)
+            .unwrap_or_else(|| self.insert_group(GroupedStatement::new(to.to_string())));
+        let group = &mut self.grouped[index];
+        group.statements.push(statement);
+        group.inherit_category();
+        group.sort();
+    }
+
+    /// Moves a statement to the group `to`, which is categorised as `account` when it is new
+    pub(super) fn book(&mut self, from: &str, item_id: &str, to: &str, account: &str) {
+        if self.statement_mut(from, item_id).is_none() {
+            return;
+        }
+        if self.group_index(to).is_none() {
+            let mut group = GroupedStatement::new(to.to_string());
+            group.category = Some(account.to_string());
+            self.insert_group(group);
+        }
+        self.move_item(from, item_id, to);
+    }
+
+    /// Groups the statements again, the deleted ones stay where they are
+    pub fn regroup(&mut self, strategy: GroupingStrategy) {
+        let deleted = self.group_index("deleted").map(|x| self.grouped.remove(x));
+        let items = self.grouped.drain(..).flat_map(|x| x.statements).collect();
+
+        self.grouped = strategy.group(items);
+        self.grouped
+            .push(deleted.unwrap_or_else(|| GroupedStatement::new("deleted".to_string())));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn statement(day: u32, cents: isize, counterparty: &str) -> Statement {
+        let mut statement = Statement::new(NaiveDate::from_ymd_opt(2023, 1, day).unwrap(), cents);
+        statement.tegenpartij = Some(counterparty.to_string());
+        statement
+    }
+
+    fn keys(scan: &Scan) -> Vec<&str> {
+        scan.grouped.iter().map(|x| x.key.as_str()).collect()
+    }
+
+    #[test]
+    fn strategy_keys() {
+        let mut shop = statement(3, -1250, "Colruyt Gent NV 123");
+        assert_eq!(
+            GroupingStrategy::LabelCounterparty.key(&shop),
+            " Colruyt Gent NV 123"
+        );
+        assert_eq!(
+            GroupingStrategy::Counterparty.key(&shop),
+            "COLRUYT GENT NV 123"
+        );
+        assert_eq!(GroupingStrategy::NormalisedPayee.key(&shop), "COLRUYT GENT");
+        assert_eq!(GroupingStrategy::Iban.key(&shop), "COLRUYT GENT NV 123");
+
+        shop.counter_account = Some("be71096123456769".to_string());
+        assert_eq!(GroupingStrategy::Iban.key(&shop), "BE71 0961 2345 6769");
+
+        // An alias wins, except over the IBAN
+        shop.payee = Some("Colruyt".to_string());
+        assert_eq!(GroupingStrategy::Counterparty.key(&shop), "Colruyt");
+        assert_eq!(GroupingStrategy::Iban.key(&shop), "BE71 0961 2345 6769");
+
+        assert_eq!(
+            GroupingStrategy::by_name("iban"),
+            Some(GroupingStrategy::Iban)
+        );
+        assert_eq!(GroupingStrategy::by_name("payee"), None);
+    }
+
+    #[test]
+    fn group_by_strategy() {
+        let mut food = statement(4, -1000, "Colruyt Gent NV");
+        food.category = Some("Expenses:Food".to_string());
+        let mut other = statement(3, -500, "COLRUYT GENT");
+        other.category = Some("Expenses:Home".to_string());
+        let items = vec![food, other, statement(5, -3000, "Proximus")];
+
+        let grouped = GroupingStrategy::Counterparty.group(items.clone());
+        let keys: Vec<_> = grouped.iter().map(|x| x.key.as_str()).collect();
+        assert_eq!(keys, ["PROXIMUS", "COLRUYT GENT NV", "COLRUYT GENT"]);
+
+        let grouped = GroupingStrategy::NormalisedPayee.group(items);
+        assert_eq!(grouped[1].key, "COLRUYT GENT");
+        assert_eq!(grouped[1].statements.len(), 2);
+        // Sorted by date
+        assert_eq!(grouped[1].statements[0].amount, -500);
+        // The statements disagree, so the group is not categorised
+        assert_eq!(grouped[1].category, None);
+    }
+
+    #[test]
+    fn merge_move_and_regroup() {
+        let items = vec![
+            statement(3, -300, "A"),
+            statement(4, -200, "B"),
+            statement(5, -100, "C"),
+        ];
+        let mut scan = Scan::new(items, &[], &[], GroupingStrategy::Counterparty);
+        scan.categorise("C", "Expenses:Food");
+
+        scan.merge("A", "C");
+        assert_eq!(keys(&scan), ["B", "C", "deleted"]);
+        let c = &scan.grouped[1];
+        assert_eq!(c.statements.len(), 2);
+        assert!(c.statements.iter().all(|x| x.category == c.category));
+
+        scan.merge("B", "deleted");
+        scan.merge("B", "missing");
+        assert_eq!(keys(&scan), ["B", "C", "deleted"]);
+
+        let id = scan.grouped[0].statements[0].id.0.clone();
+        scan.move_item("B", &id, " D ");
+        assert_eq!(keys(&scan), ["C", "D", "deleted"]);
+        assert_eq!(scan.grouped[1].statements[0].id.0, id);
+
+        let id = scan.grouped[0].statements[0].id.0.clone();
+        scan.delete_item("C", &id);
+        scan.regroup(GroupingStrategy::Counterparty);
+        assert_eq!(keys(&scan), ["B", "C", "deleted"]);
+        assert_eq!(scan.trash().count(), 1);
+    }
+}
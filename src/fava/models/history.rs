@@ -0,0 +1,233 @@
+use rocket::serde::{Deserialize, Serialize};
+
+use super::{GroupedStatement, Scan};
+
+/// Older edits are forgotten
+const MAX_EVENTS: usize = 50;
+
+/// Older edits are forgotten as well when the events hold more statements than this together,
+/// a regroup changes every group of the scan
+const MAX_STATEMENTS: usize = 5000;
+
+/// An edit of a scan, with what the groups were on the other side of the edit.
+/// Only the groups that differ are kept, the others are taken from the scan.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Event {
+    pub action: String,
+    pub time: String,
+    /// The group keys in order, empty for events that hold every group
+    #[serde(default)]
+    order: Vec<String>,
+    groups: Vec<GroupedStatement>,
+}
+
+impl Event {
+    /// What it takes to go from `to` back to `from`
+    fn new(
+        action: String,
+        time: String,
+        from: &[GroupedStatement],
+        to: &[GroupedStatement],
+    ) -> Self {
+        let groups = from
+            .iter()
+            .filter(|group| to.iter().find(|x| x.key == group.key) != Some(*group))
+            .cloned()
+            .collect();
+        Self {
+            action,
+            time,
+            order: from.iter().map(|x| x.key.clone()).collect(),
+            groups,
+        }
+    }
+
+    /// The groups on the other side of the event, starting from `current`
+    fn apply(&self, current: &[GroupedStatement]) -> Vec<GroupedStatement> {
+        if self.order.is_empty() {
+            return self.groups.clone();
+        }
+        self.order
+            .iter()
+            .filter_map(|key| {
+                self.groups
+                    .iter()
+                    .chain(current.iter())
+                    .find(|x| &x.key == key)
+                    .cloned()
+            })
+            .collect()
+    }
+
+    /// Replays the event on `scan`, returns the event that turns it back
+    fn replay(self, scan: &mut Scan) -> Event {
+        let groups = self.apply(&scan.grouped);
+        let back = Event::new(self.action, self.time, &scan.grouped, &groups);
+        scan.grouped = groups;
+        back
+    }
+
+    fn statements(&self) -> usize {
+        self.groups.iter().map(|x| x.statements.len()).sum()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct History {
+    pub done: Vec<Event>,
+    pub undone: Vec<Event>,
+}
+
+impl History {
+    fn forget(&mut self) {
+        let stored = |h: &History| -> usize {
+            h.done
+                .iter()
+                .chain(h.undone.iter())
+                .map(Event::statements)
+                .sum()
+        };
+        while self.done.len() > MAX_EVENTS || (self.done.len() > 1 && stored(self) > MAX_STATEMENTS)
+        {
+            self.done.remove(0);
+        }
+    }
+}
+
+impl Scan {
+    /// Runs `f` as one undoable edit, edits that change nothing are not recorded
+    pub fn edit<T>(&mut self, action: impl Into<String>, f: impl FnOnce(&mut Scan) -> T) -> T {
+        let before = self.grouped.clone();
+        let out = f(self);
+
+        if self.grouped != before {
+            let time = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+            let event = Event::new(action.into(), time, &before, &self.grouped);
+            self.history.done.push(event);
+            self.history.undone.clear();
+            self.history.forget();
+        }
+
+        out
+    }
+
+    pub fn undo(&mut self) -> bool {
+        match self.history.done.pop() {
+            Some(event) => {
+                let back = event.replay(self);
+                self.history.undone.push(back);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        match self.history.undone.pop() {
+            Some(event) => {
+                let back = event.replay(self);
+                self.history.done.push(back);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::super::{GroupingStrategy, Statement};
+    use super::*;
+
+    /// Groups A, B and C, sorted by total
+    fn scan() -> Scan {
+        let statements = [("A", -300), ("B", -200), ("C", -100)]
+            .iter()
+            .map(|(name, cents)| {
+                let mut statement =
+                    Statement::new(NaiveDate::from_ymd_opt(2023, 1, 3).unwrap(), *cents);
+                statement.tegenpartij = Some(name.to_string());
+                statement
+            })
+            .collect();
+        Scan::new(statements, &[], &[], GroupingStrategy::Counterparty)
+    }
+
+    fn keys(scan: &Scan) -> Vec<&str> {
+        scan.grouped.iter().map(|x| x.key.as_str()).collect()
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut scan = scan();
+        let start = scan.grouped.clone();
+        assert_eq!(keys(&scan), ["A", "B", "C", "deleted"]);
+
+        scan.edit("Categorise A", |scan| scan.categorise("A", "Expenses:Food"));
+        scan.edit("Categorise nothing", |scan| {
+            scan.categorise("X", "Expenses:Food")
+        });
+        assert_eq!(scan.history.done.len(), 1);
+
+        scan.edit("Merge A into C", |scan| scan.merge("A", "C"));
+        assert_eq!(keys(&scan), ["B", "C", "deleted"]);
+        let merged = scan.grouped.clone();
+        // Only the groups that changed are kept
+        assert_eq!(scan.history.done[1].groups.len(), 2);
+
+        assert!(scan.undo());
+        assert_eq!(keys(&scan), ["A", "B", "C", "deleted"]);
+        assert_eq!(scan.grouped[0].category.as_deref(), Some("Expenses:Food"));
+        assert!(scan.undo());
+        assert_eq!(scan.grouped, start);
+        assert!(!scan.undo());
+
+        assert!(scan.redo());
+        assert!(scan.redo());
+        assert_eq!(scan.grouped, merged);
+        assert!(!scan.redo());
+
+        scan.undo();
+        scan.edit("Delete B", |scan| scan.delete("B"));
+        assert!(scan.history.undone.is_empty());
+        assert!(!scan.redo());
+    }
+
+    #[test]
+    fn forget_old_edits() {
+        let mut scan = scan();
+        for i in 0..MAX_EVENTS + 10 {
+            let account = format!("Expenses:{}", i);
+            scan.edit("Categorise A", |scan| scan.categorise("A", &account));
+        }
+        assert_eq!(scan.history.done.len(), MAX_EVENTS);
+
+        while scan.undo() {}
+        assert_eq!(scan.grouped[0].category.as_deref(), Some("Expenses:9"));
+    }
+
+    #[test]
+    fn undo_full_snapshot() {
+        let mut scan = scan();
+        let start = scan.grouped.clone();
+        scan.merge("A", "B");
+        scan.merge("B", "C");
+
+        // Events written before only the changes were kept hold every group
+        let event = rocket::serde::json::serde_json::json!({
+            "action": "Merge",
+            "time": "2023-01-03 12:00:00",
+            "groups": start,
+        });
+        scan.history
+            .done
+            .push(rocket::serde::json::from_value(event).unwrap());
+
+        assert!(scan.undo());
+        assert_eq!(scan.grouped, start);
+        assert!(scan.redo());
+        assert_eq!(keys(&scan), ["C", "deleted"]);
+    }
+}
@@ -0,0 +1,90 @@
+use super::Statement;
+
+/// Without spaces or dashes and in upper case, `be71 0961-2345 6769` is `BE71096123456769`
+pub fn normalise(account: &str) -> String {
+    account
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/// Checks the length, the country and the mod-97 checksum of a normalised IBAN
+pub fn is_valid(iban: &str) -> bool {
+    let bytes = iban.as_bytes();
+    if !(15..=34).contains(&bytes.len())
+        || !bytes[..2].iter().all(u8::is_ascii_uppercase)
+        || !bytes[2..4].iter().all(u8::is_ascii_digit)
+        || !bytes.iter().all(u8::is_ascii_alphanumeric)
+    {
+        return false;
+    }
+
+    // The country and check digits move to the back, letters count as 10 to 35
+    let remainder = bytes[4..].iter().chain(&bytes[..4]).fold(0u32, |acc, b| {
+        let value = match b {
+            b'0'..=b'9' => (b - b'0') as u32,
+            _ => (b - b'A') as u32 + 10,
+        };
+        if value < 10 {
+            (acc * 10 + value) % 97
+        } else {
+            (acc * 100 + value) % 97
+        }
+    });
+    remainder == 1
+}
+
+/// In groups of four, the way it is printed on paper
+pub fn format(iban: &str) -> String {
+    iban.as_bytes()
+        .chunks(4)
+        .map(|x| String::from_utf8_lossy(x))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Bank, country, location and an optional branch: 8 or 11 characters
+pub fn is_valid_bic(bic: &str) -> bool {
+    let bytes = bic.as_bytes();
+    (bytes.len() == 8 || bytes.len() == 11)
+        && bytes[..6].iter().all(u8::is_ascii_uppercase)
+        && bytes[6..].iter().all(u8::is_ascii_alphanumeric)
+}
+
+impl Statement {
+    /// The account of the counterparty, only when it is an IBAN with a valid checksum
+    pub fn iban(&self) -> Option<String> {
+        self.counter_account
+            .as_deref()
+            .map(normalise)
+            .filter(|x| is_valid(x))
+    }
+
+    pub fn bic(&self) -> Option<String> {
+        self.bic
+            .as_deref()
+            .map(normalise)
+            .filter(|x| is_valid_bic(x))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksum() {
+        assert!(is_valid(&normalise("BE71 0961 2345 6769")));
+        assert!(is_valid(&normalise("be68-5390-0754-7034")));
+        assert!(is_valid("GB82WEST12345698765432"));
+        assert!(!is_valid(&normalise("BE71 0961 2345 6768")));
+        assert!(!is_valid("BE71"));
+        assert!(!is_valid("1234567890123456"));
+
+        assert_eq!(format("BE71096123456769"), "BE71 0961 2345 6769");
+        assert!(is_valid_bic("GKCCBEBB"));
+        assert!(is_valid_bic("DEUTDEFF500"));
+        assert!(!is_valid_bic("GKCC"));
+    }
+}
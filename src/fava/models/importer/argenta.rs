@@ -0,0 +1,100 @@
+use chrono::NaiveDate;
+use csv::StringRecord;
+use rocket::serde::Deserialize;
+
+use super::super::{money, my_date, Description, Note, Statement};
+use super::Importer;
+
+pub struct Argenta;
+
+#[derive(Deserialize, Debug, Clone)]
+struct ArgentaStatement {
+    #[serde(rename = "Beschrijving")]
+    #[serde(deserialize_with = "my_date::deserialize_betaling")]
+    description: Result<Description, String>,
+    #[serde(rename = "Bedrag v/d verrichting")]
+    #[serde(deserialize_with = "money::deserialize")]
+    amount: isize,
+    #[serde(rename = "Munt")]
+    #[serde(default = "money::default_currency")]
+    #[serde(deserialize_with = "money::deserialize_currency")]
+    currency: String,
+    #[serde(rename = "Saldo")]
+    #[serde(default)]
+    #[serde(deserialize_with = "money::deserialize_balance")]
+    balance: Option<isize>,
+    #[serde(rename = "Nr v/d verrichting")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    reference: Option<String>,
+    #[serde(rename = "Nr tegenpartij")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    counter_account: Option<String>,
+    #[serde(rename = "Nr v/d rekening")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    own_account: Option<String>,
+    #[serde(rename = "BIC tegenpartij")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    bic: Option<String>,
+    #[serde(rename = "Boekdatum")]
+    #[serde(deserialize_with = "my_date::deserialize_lenient")]
+    date: NaiveDate,
+    #[serde(rename = "Naam v/d tegenpartij :")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    tegenpartij: Option<String>,
+    #[serde(rename = "Mededeling 1 :")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    note: Option<String>,
+    #[serde(rename = "Mededeling 2 :")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    note_extra: Option<String>,
+}
+
+impl ArgentaStatement {
+    fn into(self) -> Statement {
+        let note = match (self.note, self.note_extra) {
+            (Some(a), Some(b)) => Some(format!("{} {}", a, b)),
+            (a, b) => a.or(b),
+        };
+
+        Statement {
+            description: self.description.ok(),
+            currency: self.currency,
+            balance: self.balance,
+            reference: self.reference,
+            counter_account: self.counter_account,
+            own_account: self.own_account,
+            bic: self.bic,
+            tegenpartij: self.tegenpartij,
+            note: Note::from_text(note),
+            ..Statement::new(self.date, self.amount)
+        }
+    }
+}
+
+impl Importer for Argenta {
+    fn name(&self) -> &'static str {
+        "argenta"
+    }
+
+    fn headers(&self) -> &'static [&'static str] {
+        &[
+            "Nr v/d rekening",
+            "Boekdatum",
+            "Beschrijving",
+            "Bedrag v/d verrichting",
+        ]
+    }
+
+    fn read(&self, record: &StringRecord, headers: &StringRecord) -> Result<Statement, csv::Error> {
+        record
+            .deserialize::<ArgentaStatement>(Some(headers))
+            .map(ArgentaStatement::into)
+    }
+}
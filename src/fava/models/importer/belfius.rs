@@ -0,0 +1,91 @@
+use chrono::NaiveDate;
+use csv::StringRecord;
+use rocket::serde::Deserialize;
+
+use super::super::{money, my_date, Description, Note, Statement};
+use super::Importer;
+
+pub struct Belfius;
+
+#[derive(Deserialize, Debug, Clone)]
+struct BelfiusStatement {
+    #[serde(rename = "Transactie")]
+    #[serde(deserialize_with = "my_date::deserialize_betaling")]
+    description: Result<Description, String>,
+    #[serde(rename = "Bedrag")]
+    #[serde(deserialize_with = "money::deserialize")]
+    amount: isize,
+    #[serde(rename = "Devies")]
+    #[serde(alias = "Munt")]
+    #[serde(default = "money::default_currency")]
+    #[serde(deserialize_with = "money::deserialize_currency")]
+    currency: String,
+    #[serde(rename = "Saldo")]
+    #[serde(default)]
+    #[serde(deserialize_with = "money::deserialize_balance")]
+    balance: Option<isize>,
+    #[serde(rename = "Transactienummer")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    reference: Option<String>,
+    #[serde(rename = "Rekening tegenpartij")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    counter_account: Option<String>,
+    #[serde(rename = "Rekening")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    own_account: Option<String>,
+    #[serde(rename = "BIC")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    bic: Option<String>,
+    #[serde(rename = "Boekingsdatum")]
+    #[serde(deserialize_with = "my_date::deserialize_lenient")]
+    date: NaiveDate,
+    #[serde(rename = "Naam tegenpartij bevat")]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    tegenpartij: Option<String>,
+    #[serde(rename = "Mededelingen")]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    note: Option<String>,
+}
+
+impl BelfiusStatement {
+    fn into(self) -> Statement {
+        Statement {
+            description: self.description.ok(),
+            currency: self.currency,
+            balance: self.balance,
+            reference: self.reference,
+            counter_account: self.counter_account,
+            own_account: self.own_account,
+            bic: self.bic,
+            tegenpartij: self.tegenpartij,
+            note: Note::from_text(self.note),
+            ..Statement::new(self.date, self.amount)
+        }
+    }
+}
+
+impl Importer for Belfius {
+    fn name(&self) -> &'static str {
+        "belfius"
+    }
+
+    fn headers(&self) -> &'static [&'static str] {
+        &[
+            "Boekingsdatum",
+            "Naam tegenpartij bevat",
+            "Transactie",
+            "Bedrag",
+            "Mededelingen",
+        ]
+    }
+
+    fn read(&self, record: &StringRecord, headers: &StringRecord) -> Result<Statement, csv::Error> {
+        record
+            .deserialize::<BelfiusStatement>(Some(headers))
+            .map(BelfiusStatement::into)
+    }
+}
@@ -0,0 +1,93 @@
+use chrono::NaiveDate;
+use csv::StringRecord;
+use rocket::serde::Deserialize;
+
+use super::super::{money, my_date, Description, Note, Statement};
+use super::Importer;
+
+pub struct BnpParibasFortis;
+
+#[derive(Deserialize, Debug, Clone)]
+struct BnpStatement {
+    #[serde(rename = "Details")]
+    #[serde(deserialize_with = "my_date::deserialize_betaling")]
+    description: Result<Description, String>,
+    #[serde(rename = "Bedrag")]
+    #[serde(deserialize_with = "money::deserialize")]
+    amount: isize,
+    #[serde(rename = "Valuta rekening")]
+    #[serde(alias = "Munt")]
+    #[serde(default = "money::default_currency")]
+    #[serde(deserialize_with = "money::deserialize_currency")]
+    currency: String,
+    #[serde(rename = "Saldo")]
+    #[serde(default)]
+    #[serde(deserialize_with = "money::deserialize_balance")]
+    balance: Option<isize>,
+    #[serde(rename = "Volgnummer")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    reference: Option<String>,
+    #[serde(rename = "Tegenpartij")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    counter_account: Option<String>,
+    #[serde(rename = "Rekeningnummer")]
+    #[serde(alias = "Rekening")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    own_account: Option<String>,
+    #[serde(rename = "BIC tegenpartij")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    bic: Option<String>,
+    #[serde(rename = "Uitvoeringsdatum")]
+    #[serde(deserialize_with = "my_date::deserialize_lenient")]
+    date: NaiveDate,
+    #[serde(rename = "Naam van de tegenpartij")]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    tegenpartij: Option<String>,
+    #[serde(rename = "Mededeling")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    note: Option<String>,
+}
+
+impl BnpStatement {
+    fn into(self) -> Statement {
+        Statement {
+            description: self.description.ok(),
+            currency: self.currency,
+            balance: self.balance,
+            reference: self.reference,
+            counter_account: self.counter_account,
+            own_account: self.own_account,
+            bic: self.bic,
+            tegenpartij: self.tegenpartij,
+            note: Note::from_text(self.note),
+            ..Statement::new(self.date, self.amount)
+        }
+    }
+}
+
+impl Importer for BnpParibasFortis {
+    fn name(&self) -> &'static str {
+        "bnp"
+    }
+
+    fn headers(&self) -> &'static [&'static str] {
+        &[
+            "Volgnummer",
+            "Uitvoeringsdatum",
+            "Bedrag",
+            "Naam van de tegenpartij",
+            "Details",
+        ]
+    }
+
+    fn read(&self, record: &StringRecord, headers: &StringRecord) -> Result<Statement, csv::Error> {
+        record
+            .deserialize::<BnpStatement>(Some(headers))
+            .map(BnpStatement::into)
+    }
+}
@@ -0,0 +1,333 @@
+use chrono::NaiveDate;
+use regex::Regex;
+use roxmltree::{Document, Node};
+
+use super::super::{money, Note, Statement};
+use super::{decimal_cents, line_at, DocumentImporter, Entry, ImportError, Shared};
+
+/// ISO 20022 bank to customer statement, every `Ntry` is a statement
+pub struct Camt053;
+
+/// Banks fill in references they don't have with this
+const NOT_PROVIDED: &str = "NOTPROVIDED";
+
+/// The first element found by following `path`, namespaces are ignored
+fn child<'a, 'input>(node: Node<'a, 'input>, path: &[&str]) -> Option<Node<'a, 'input>> {
+    path.iter().try_fold(node, |node, name| {
+        node.children()
+            .find(|x| x.is_element() && x.tag_name().name() == *name)
+    })
+}
+
+fn text(node: Node, path: &[&str]) -> Option<String> {
+    child(node, path)?
+        .text()
+        .map(|x| x.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|x| !x.is_empty())
+}
+
+/// `Dt` or the date part of `DtTm`
+fn date(node: Node, name: &str) -> Option<NaiveDate> {
+    let date = text(node, &[name, "Dt"]).or_else(|| text(node, &[name, "DtTm"]))?;
+    NaiveDate::parse_from_str(date.get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// The name of a party, older versions don't have the `Pty` level
+fn name(party: Node) -> Option<String> {
+    text(party, &["Nm"]).or_else(|| text(party, &["Pty", "Nm"]))
+}
+
+/// The booked balance at the end of the statement
+fn closing_balance(statement: Node) -> Option<isize> {
+    let balance = statement.children().find(|x| {
+        x.tag_name().name() == "Bal"
+            && text(*x, &["Tp", "CdOrPrtry", "Cd"]).as_deref() == Some("CLBD")
+    })?;
+    let cents = decimal_cents(&text(balance, &["Amt"])?).ok()?;
+    match text(balance, &["CdtDtInd"]).as_deref() {
+        Some("DBIT") => Some(-cents),
+        _ => Some(cents),
+    }
+}
+
+fn iban(account: Node) -> Option<String> {
+    text(account, &["Id", "IBAN"]).or_else(|| text(account, &["Id", "Othr", "Id"]))
+}
+
+impl DocumentImporter for Camt053 {
+    fn name(&self) -> &'static str {
+        "camt053"
+    }
+
+    fn detect(&self, content: &str) -> bool {
+        content.contains("BkToCstmrStmt>") || content.contains("camt.053")
+    }
+
+    fn split(&self, content: &str) -> Result<Vec<Entry>, ImportError> {
+        let document = self.parse(content)?;
+        Ok(entries(&document, content)
+            .into_iter()
+            .map(|(_, entry)| entry)
+            .collect())
+    }
+
+    /// A hand fixed entry, the prefixes it uses are declared around it
+    fn read(&self, entry: &str, shared: &Shared) -> Result<Statement, String> {
+        let wrapped = declare_prefixes(entry);
+        let document = Document::parse(&wrapped).map_err(|e| e.to_string())?;
+        let entry = document
+            .root_element()
+            .first_element_child()
+            .ok_or("Empty entry.")?;
+        read_entry(entry, shared)
+    }
+
+    fn read_all(
+        &self,
+        content: &str,
+    ) -> Result<Vec<(Entry, Result<Statement, String>)>, ImportError> {
+        let document = self.parse(content)?;
+        Ok(entries(&document, content)
+            .into_iter()
+            .map(|(node, entry)| {
+                let statement = read_entry(node, &entry.shared);
+                (entry, statement)
+            })
+            .collect())
+    }
+}
+
+impl Camt053 {
+    fn parse<'input>(&self, content: &'input str) -> Result<Document<'input>, ImportError> {
+        Document::parse(content).map_err(|e| ImportError::Document(self.name(), e.to_string()))
+    }
+}
+
+/// Every `Ntry` of every `Stmt` with its entry, the last entry of a statement has its balance
+fn entries<'a, 'input>(
+    document: &'a Document<'input>,
+    content: &str,
+) -> Vec<(Node<'a, 'input>, Entry)> {
+    let mut entries: Vec<(Node, Entry)> = Vec::new();
+    for statement in document
+        .descendants()
+        .filter(|x| x.is_element() && x.tag_name().name() == "Stmt")
+    {
+        let account = child(statement, &["Acct"]);
+        let shared = Shared {
+            currency: account.and_then(|x| text(x, &["Ccy"])),
+            account: account.and_then(iban),
+            balance: None,
+        };
+
+        let start = entries.len();
+        entries.extend(
+            statement
+                .children()
+                .filter(|x| x.is_element() && x.tag_name().name() == "Ntry")
+                .map(|x| {
+                    let entry = Entry {
+                        line: line_at(content, x.range().start),
+                        raw: content[x.range()].to_string(),
+                        shared: shared.clone(),
+                    };
+                    (x, entry)
+                }),
+        );
+        if let Some((_, last)) = entries[start..].last_mut() {
+            last.shared.balance = closing_balance(statement);
+        }
+    }
+    entries
+}
+
+/// An entry on its own loses the namespace declarations of the document (`<ns2:Ntry>`).
+/// Names are matched without their namespace, so any declaration will do.
+fn declare_prefixes(entry: &str) -> String {
+    let mut prefixes: Vec<&str> = PREFIX
+        .captures_iter(entry)
+        .filter_map(|x| x.get(1))
+        .map(|x| x.as_str())
+        .filter(|x| *x != "xml" && *x != "xmlns")
+        .collect();
+    prefixes.sort_unstable();
+    prefixes.dedup();
+
+    let declarations: String = prefixes
+        .iter()
+        .map(|x| format!(" xmlns:{}=\"urn:entry:{}\"", x, x))
+        .collect();
+    format!("<Entry{}>{}</Entry>", declarations, entry)
+}
+
+lazy_static! {
+    /// Prefixes of elements and attributes
+    static ref PREFIX: Regex = Regex::new(r"[<\s/]([A-Za-z_][\w.-]*):[A-Za-z_]").unwrap();
+}
+
+fn read_entry(entry: Node, shared: &Shared) -> Result<Statement, String> {
+    let amount = child(entry, &["Amt"]).ok_or("Ntry without Amt.")?;
+    let cents = decimal_cents(amount.text().unwrap_or(""))?;
+    let debit = match text(entry, &["CdtDtInd"]).as_deref() {
+        Some("DBIT") => true,
+        Some("CRDT") => false,
+        _ => return Err("CdtDtInd is not CRDT or DBIT.".to_string()),
+    };
+    let currency = amount
+        .attribute("Ccy")
+        .map(str::to_uppercase)
+        .or_else(|| shared.currency.clone())
+        .unwrap_or_else(money::default_currency);
+
+    let booked = date(entry, "BookgDt")
+        .or_else(|| date(entry, "ValDt"))
+        .ok_or("Ntry without BookgDt.")?;
+
+    let details = child(entry, &["NtryDtls", "TxDtls"]);
+    let detail = |path: &[&str]| details.and_then(|x| text(x, path));
+
+    // The counterparty is the creditor of what we pay and the debtor of what we receive
+    let (party, account, agent) = if debit {
+        ("Cdtr", "CdtrAcct", "CdtrAgt")
+    } else {
+        ("Dbtr", "DbtrAcct", "DbtrAgt")
+    };
+    let parties = details.and_then(|x| child(x, &["RltdPties"]));
+
+    let free: Vec<_> = details
+        .and_then(|x| child(x, &["RmtInf"]))
+        .map(|x| {
+            x.children()
+                .filter(|x| x.tag_name().name() == "Ustrd")
+                .filter_map(|x| x.text())
+                .map(str::trim)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let description = text(entry, &["AddtlNtryInf"])
+        .or_else(|| detail(&["AddtlTxInf"]))
+        .and_then(|x| x.parse().ok());
+
+    Ok(Statement {
+        description,
+        currency,
+        value_date: date(entry, "ValDt"),
+        own_account: shared.account.clone(),
+        balance: shared.balance,
+        reference: text(entry, &["AcctSvcrRef"]).or_else(|| detail(&["Refs", "AcctSvcrRef"])),
+        end_to_end: detail(&["Refs", "EndToEndId"]).filter(|x| x != NOT_PROVIDED),
+        counter_account: parties.and_then(|x| child(x, &[account])).and_then(iban),
+        bic: details
+            .and_then(|x| child(x, &["RltdAgts", agent, "FinInstnId"]))
+            .and_then(|x| text(x, &["BICFI"]).or_else(|| text(x, &["BIC"]))),
+        tegenpartij: parties.and_then(|x| child(x, &[party])).and_then(name),
+        note: Note {
+            structured: detail(&["RmtInf", "Strd", "CdtrRefInf", "Ref"]),
+            free: Some(free.join(" ")).filter(|x| !x.is_empty()),
+        },
+        ..Statement::new(booked, if debit { -cents } else { cents })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::super::read;
+
+    /// Prefixed elements, one entry with an amount that can't be read
+    const DOCUMENT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ns2:Document xmlns:ns2="urn:iso:std:iso:20022:tech:xsd:camt.053.001.02">
+  <ns2:BkToCstmrStmt>
+    <ns2:Stmt>
+      <ns2:Acct><ns2:Id><ns2:IBAN>BE68539007547034</ns2:IBAN></ns2:Id><ns2:Ccy>EUR</ns2:Ccy></ns2:Acct>
+      <ns2:Bal>
+        <ns2:Tp><ns2:CdOrPrtry><ns2:Cd>CLBD</ns2:Cd></ns2:CdOrPrtry></ns2:Tp>
+        <ns2:Amt Ccy="EUR">1205.50</ns2:Amt>
+        <ns2:CdtDtInd>CRDT</ns2:CdtDtInd>
+      </ns2:Bal>
+      <ns2:Ntry>
+        <ns2:Amt Ccy="EUR">45.00</ns2:Amt>
+        <ns2:CdtDtInd>DBIT</ns2:CdtDtInd>
+        <ns2:BookgDt><ns2:Dt>2023-02-03</ns2:Dt></ns2:BookgDt>
+        <ns2:ValDt><ns2:Dt>2023-02-02</ns2:Dt></ns2:ValDt>
+        <ns2:AcctSvcrRef>REF-0001</ns2:AcctSvcrRef>
+        <ns2:NtryDtls><ns2:TxDtls>
+          <ns2:Refs><ns2:EndToEndId>E2E-42</ns2:EndToEndId></ns2:Refs>
+          <ns2:RltdPties>
+            <ns2:Cdtr><ns2:Nm>Proximus NV</ns2:Nm></ns2:Cdtr>
+            <ns2:CdtrAcct><ns2:Id><ns2:IBAN>BE43068999999501</ns2:IBAN></ns2:Id></ns2:CdtrAcct>
+          </ns2:RltdPties>
+          <ns2:RmtInf><ns2:Strd><ns2:CdtrRefInf><ns2:Ref>090933755493</ns2:Ref></ns2:CdtrRefInf></ns2:Strd></ns2:RmtInf>
+        </ns2:TxDtls></ns2:NtryDtls>
+      </ns2:Ntry>
+      <ns2:Ntry>
+        <ns2:Amt Ccy="EUR">12.345</ns2:Amt>
+        <ns2:CdtDtInd>DBIT</ns2:CdtDtInd>
+        <ns2:BookgDt><ns2:Dt>2023-02-20</ns2:Dt></ns2:BookgDt>
+      </ns2:Ntry>
+      <ns2:Ntry>
+        <ns2:Amt Ccy="EUR">1250.5</ns2:Amt>
+        <ns2:CdtDtInd>CRDT</ns2:CdtDtInd>
+        <ns2:BookgDt><ns2:DtTm>2023-02-25T10:00:00</ns2:DtTm></ns2:BookgDt>
+        <ns2:NtryDtls><ns2:TxDtls>
+          <ns2:Refs><ns2:EndToEndId>NOTPROVIDED</ns2:EndToEndId></ns2:Refs>
+          <ns2:RltdPties><ns2:Dbtr><ns2:Nm>Werkgever BV</ns2:Nm></ns2:Dbtr></ns2:RltdPties>
+          <ns2:RmtInf><ns2:Ustrd>Loon</ns2:Ustrd><ns2:Ustrd>februari</ns2:Ustrd></ns2:RmtInf>
+        </ns2:TxDtls></ns2:NtryDtls>
+      </ns2:Ntry>
+    </ns2:Stmt>
+  </ns2:BkToCstmrStmt>
+</ns2:Document>"#;
+
+    #[test]
+    fn read_entries() {
+        let mut import = read(DOCUMENT, None).unwrap();
+        assert_eq!(import.bank, "camt053");
+        assert_eq!(import.statements.len(), 2);
+
+        let paid = &import.statements[0];
+        assert_eq!(paid.amount, -4500);
+        assert_eq!(paid.date, NaiveDate::from_ymd_opt(2023, 2, 3).unwrap());
+        assert_eq!(paid.value_date, NaiveDate::from_ymd_opt(2023, 2, 2));
+        assert_eq!(paid.own_account.as_deref(), Some("BE68539007547034"));
+        assert_eq!(paid.reference.as_deref(), Some("REF-0001"));
+        assert_eq!(paid.end_to_end.as_deref(), Some("E2E-42"));
+        assert_eq!(paid.tegenpartij.as_deref(), Some("Proximus NV"));
+        assert_eq!(paid.counter_account.as_deref(), Some("BE43068999999501"));
+        assert_eq!(paid.ogm().as_deref(), Some("090933755493"));
+        assert_eq!(paid.balance, None);
+
+        let received = &import.statements[1];
+        assert_eq!(received.amount, 125050);
+        assert_eq!(received.end_to_end, None);
+        assert_eq!(received.tegenpartij.as_deref(), Some("Werkgever BV"));
+        assert_eq!(received.note.free.as_deref(), Some("Loon februari"));
+        // The closing balance belongs to the last entry
+        assert_eq!(received.balance, Some(120550));
+
+        assert_eq!(import.errors.len(), 1);
+        let error = import.errors[0].clone();
+        assert_eq!(error.line, 26);
+        assert!(error.raw.starts_with("<ns2:Ntry>"));
+
+        // A hand fixed entry keeps its prefixes
+        let fixed = error.raw.replace("12.345", "12.35");
+        import.retry(error.line, &fixed).unwrap();
+        assert_eq!(import.statements[2].amount, -1235);
+        assert_eq!(import.statements[2].currency, "EUR");
+        assert!(import.errors.is_empty());
+    }
+
+    #[test]
+    fn invalid_document() {
+        assert!(read("<Document><BkToCstmrStmt>", None).is_err());
+        assert!(
+            read("<Document><BkToCstmrStmt/></Document>", Some("camt053"))
+                .unwrap()
+                .statements
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use chrono::NaiveDate;
+use csv::StringRecord;
+use rocket::serde::Deserialize;
+
+use super::super::{money, my_date, Description, Note, Statement};
+use super::Importer;
+
+pub struct Ing;
+
+#[derive(Deserialize, Debug, Clone)]
+struct IngStatement {
+    #[serde(rename = "Omschrijving")]
+    #[serde(deserialize_with = "my_date::deserialize_betaling")]
+    description: Result<Description, String>,
+    #[serde(rename = "Bedrag")]
+    #[serde(deserialize_with = "money::deserialize")]
+    amount: isize,
+    #[serde(rename = "Munteenheid")]
+    #[serde(alias = "Munt")]
+    #[serde(default = "money::default_currency")]
+    #[serde(deserialize_with = "money::deserialize_currency")]
+    currency: String,
+    #[serde(rename = "Saldo na verrichting")]
+    #[serde(alias = "Saldo")]
+    #[serde(default)]
+    #[serde(deserialize_with = "money::deserialize_balance")]
+    balance: Option<isize>,
+    #[serde(rename = "Nummer van de verrichting")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    reference: Option<String>,
+    #[serde(rename = "Rekening tegenpartij")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    counter_account: Option<String>,
+    #[serde(rename = "Rekeningnummer")]
+    #[serde(alias = "Rekening")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    own_account: Option<String>,
+    #[serde(rename = "BIC tegenpartij")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    bic: Option<String>,
+    #[serde(rename = "Boekingsdatum")]
+    #[serde(deserialize_with = "my_date::deserialize_lenient")]
+    date: NaiveDate,
+    #[serde(rename = "Detail van de omzet")]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    detail: Option<String>,
+    #[serde(rename = "Bericht")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    note: Option<String>,
+}
+
+impl IngStatement {
+    fn into(self) -> Statement {
+        // ING has no counterparty name column, the detail line starts with it
+        let tegenpartij = self
+            .detail
+            .as_deref()
+            .and_then(|x| x.split(" - ").next())
+            .map(|x| x.trim().to_string());
+
+        Statement {
+            description: self.description.ok(),
+            currency: self.currency,
+            balance: self.balance,
+            reference: self.reference,
+            counter_account: self.counter_account,
+            own_account: self.own_account,
+            bic: self.bic,
+            tegenpartij,
+            note: Note::from_text(self.note),
+            ..Statement::new(self.date, self.amount)
+        }
+    }
+}
+
+impl Importer for Ing {
+    fn name(&self) -> &'static str {
+        "ing"
+    }
+
+    fn headers(&self) -> &'static [&'static str] {
+        &[
+            "Boekingsdatum",
+            "Nummer van de verrichting",
+            "Omschrijving",
+            "Detail van de omzet",
+            "Bedrag",
+        ]
+    }
+
+    fn read(&self, record: &StringRecord, headers: &StringRecord) -> Result<Statement, csv::Error> {
+        record
+            .deserialize::<IngStatement>(Some(headers))
+            .map(IngStatement::into)
+    }
+}
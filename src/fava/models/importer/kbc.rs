@@ -0,0 +1,103 @@
+use chrono::NaiveDate;
+use csv::StringRecord;
+use rocket::serde::Deserialize;
+
+use super::super::{money, my_date, Description, Note, Statement};
+use super::Importer;
+
+pub struct Kbc;
+
+#[derive(Deserialize, Debug, Clone)]
+struct KbcStatement {
+    #[serde(rename = "Omschrijving")]
+    #[serde(deserialize_with = "my_date::deserialize_betaling")]
+    description: Result<Description, String>,
+    #[serde(rename = "Bedrag")]
+    #[serde(deserialize_with = "money::deserialize")]
+    amount: isize,
+    #[serde(rename = "Munt")]
+    #[serde(default = "money::default_currency")]
+    #[serde(deserialize_with = "money::deserialize_currency")]
+    currency: String,
+    #[serde(rename = "Saldo")]
+    #[serde(default)]
+    #[serde(deserialize_with = "money::deserialize_balance")]
+    balance: Option<isize>,
+    #[serde(rename = "rekeningnummer tegenpartij")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    counter_account: Option<String>,
+    #[serde(rename = "Rekeningnummer")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    own_account: Option<String>,
+    #[serde(rename = "BIC tegenpartij")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    bic: Option<String>,
+    #[serde(rename = "Datum")]
+    #[serde(with = "my_date")]
+    date: NaiveDate,
+    #[serde(rename = "Naam tegenpartij")]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    tegenpartij: Option<String>,
+    #[serde(rename = "gestructureerde mededeling")]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    structured: Option<String>,
+    #[serde(rename = "Vrije mededeling")]
+    #[serde(default)]
+    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
+    free: Option<String>,
+}
+
+impl KbcStatement {
+    fn into(self) -> Statement {
+        let KbcStatement {
+            description,
+            amount,
+            currency,
+            balance,
+            counter_account,
+            own_account,
+            bic,
+            date,
+            tegenpartij,
+            structured,
+            free,
+        } = self;
+        // KBC has no reference per transaction, `Afschriftnummer` is shared by a whole statement
+        Statement {
+            description: description.ok(),
+            currency,
+            balance,
+            counter_account,
+            own_account,
+            bic,
+            tegenpartij,
+            note: Note { structured, free },
+            ..Statement::new(date, amount)
+        }
+    }
+}
+
+impl Importer for Kbc {
+    fn name(&self) -> &'static str {
+        "kbc"
+    }
+
+    fn headers(&self) -> &'static [&'static str] {
+        &[
+            "Datum",
+            "Omschrijving",
+            "Bedrag",
+            "Naam tegenpartij",
+            "gestructureerde mededeling",
+        ]
+    }
+
+    fn read(&self, record: &StringRecord, headers: &StringRecord) -> Result<Statement, csv::Error> {
+        record
+            .deserialize::<KbcStatement>(Some(headers))
+            .map(KbcStatement::into)
+    }
+}
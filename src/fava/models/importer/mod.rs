@@ -0,0 +1,413 @@
+use csv::StringRecord;
+use rocket::serde::{Deserialize, Serialize};
+
+use super::Statement;
+
+mod argenta;
+mod belfius;
+mod bnp;
+mod camt;
+mod ing;
+mod kbc;
+mod ofx;
+mod qif;
+
+pub use argenta::Argenta;
+pub use belfius::Belfius;
+pub use bnp::BnpParibasFortis;
+pub use camt::Camt053;
+pub use ing::Ing;
+pub use kbc::Kbc;
+pub use ofx::Ofx;
+pub use qif::Qif;
+
+/// Reads the CSV export of one specific bank into `Statement`s
+pub trait Importer: Sync {
+    /// Short name used to force this importer, e.g. `kbc`
+    fn name(&self) -> &'static str;
+
+    /// Columns that have to be present in the header row of this bank's export
+    fn headers(&self) -> &'static [&'static str];
+
+    fn read(&self, record: &StringRecord, headers: &StringRecord) -> Result<Statement, csv::Error>;
+
+    fn detect(&self, headers: &StringRecord) -> bool {
+        self.headers()
+            .iter()
+            .all(|h| headers.iter().any(|x| x == *h))
+    }
+}
+
+pub const IMPORTERS: &[&dyn Importer] = &[&Kbc, &Belfius, &Ing, &Argenta, &BnpParibasFortis];
+
+/// What the entries of one statement of a document have in common
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Shared {
+    pub currency: Option<String>,
+    /// Our own account
+    pub account: Option<String>,
+    /// Balance after the entry, documents only tell it for the last entry of a statement
+    #[serde(default)]
+    pub balance: Option<isize>,
+}
+
+/// An entry of a document with the line it starts on, it is read into one `Statement`
+pub struct Entry {
+    pub line: u64,
+    pub raw: String,
+    pub shared: Shared,
+}
+
+/// Reads exports that are one document instead of rows, like CAMT.053, OFX and QIF
+pub trait DocumentImporter: Sync {
+    /// Short name used to force this importer, e.g. `ofx`
+    fn name(&self) -> &'static str;
+
+    fn detect(&self, content: &str) -> bool;
+
+    /// Splits the document into its entries, a document can hold statements of several accounts
+    fn split(&self, content: &str) -> Result<Vec<Entry>, ImportError>;
+
+    fn read(&self, entry: &str, shared: &Shared) -> Result<Statement, String>;
+
+    /// Splits and reads every entry, importers that parse the whole document read from that
+    #[allow(clippy::type_complexity)]
+    fn read_all(
+        &self,
+        content: &str,
+    ) -> Result<Vec<(Entry, Result<Statement, String>)>, ImportError> {
+        Ok(self
+            .split(content)?
+            .into_iter()
+            .map(|entry| {
+                let statement = self.read(&entry.raw, &entry.shared);
+                (entry, statement)
+            })
+            .collect())
+    }
+}
+
+pub const DOCUMENT_IMPORTERS: &[&dyn DocumentImporter] = &[&Camt053, &Ofx, &Qif];
+
+const DELIMITERS: &[u8] = b";,\t";
+
+/// Some banks start their export with a few lines describing the account
+const MAX_PREAMBLE: usize = 20;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ImportError {
+    #[error("Unknown bank '{0}'.")]
+    UnknownBank(String),
+    #[error("Could not detect the bank of this export.")]
+    UnknownFormat,
+    #[error("CSV error. {0}")]
+    Csv(#[from] csv::Error),
+    #[error("Invalid {0} document. {1}")]
+    Document(&'static str, String),
+}
+
+pub fn by_name(name: &str) -> Option<&'static dyn Importer> {
+    IMPORTERS
+        .iter()
+        .find(|x| x.name().eq_ignore_ascii_case(name))
+        .copied()
+}
+
+pub fn document_by_name(name: &str) -> Option<&'static dyn DocumentImporter> {
+    DOCUMENT_IMPORTERS
+        .iter()
+        .find(|x| x.name().eq_ignore_ascii_case(name))
+        .copied()
+}
+
+/// Names of all importers, the banks first
+pub fn names() -> impl Iterator<Item = &'static str> {
+    IMPORTERS
+        .iter()
+        .map(|x| x.name())
+        .chain(DOCUMENT_IMPORTERS.iter().map(|x| x.name()))
+}
+
+/// Amounts of documents never group thousands, so `1.000` is one and not a thousand
+fn decimal_cents(input: &str) -> Result<isize, String> {
+    let invalid = || format!("Invalid amount '{}'.", input);
+
+    let number = input.trim();
+    let (negative, number) = match number.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, number.strip_prefix('+').unwrap_or(number)),
+    };
+    let (whole, fraction) = number.split_once(['.', ',']).unwrap_or((number, ""));
+    let fraction = fraction.trim_end_matches('0');
+
+    let digits = |x: &str| x.chars().all(|c| c.is_ascii_digit());
+    if whole.is_empty() || !digits(whole) || !digits(fraction) {
+        return Err(invalid());
+    }
+    if fraction.len() > 2 {
+        return Err(format!("Amount '{}' has more than two decimals.", input));
+    }
+
+    let cents = whole
+        .parse::<isize>()
+        .ok()
+        .and_then(|x| x.checked_mul(100))
+        .and_then(|x| x.checked_add(format!("{:0<2}", fraction).parse().ok()?))
+        .ok_or_else(invalid)?;
+    Ok(if negative { -cents } else { cents })
+}
+
+/// Line of a position in `content`, the first line is 1
+fn line_at(content: &str, offset: usize) -> u64 {
+    content[..offset].matches('\n').count() as u64 + 1
+}
+
+/// The result of sniffing an export: the bank, the delimiter and the header row
+pub struct Sniffed<'a> {
+    pub importer: &'static dyn Importer,
+    pub delimiter: u8,
+    pub headers: StringRecord,
+    /// Line number of the header row
+    pub line: u64,
+    /// Everything after the header row
+    pub body: &'a str,
+}
+
+fn parse_line(line: &str, delimiter: u8) -> Option<StringRecord> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .delimiter(delimiter)
+        .from_reader(line.as_bytes());
+    let mut record = rdr.records().next()?.ok()?;
+    record.trim();
+    Some(record)
+}
+
+/// Finds the header row and delimiter in the first lines of `content`.
+/// When `forced` is given only that importer is tried.
+pub fn sniff<'a>(
+    content: &'a str,
+    forced: Option<&'static dyn Importer>,
+) -> Result<Sniffed<'a>, ImportError> {
+    let candidates: Vec<&'static dyn Importer> = match forced {
+        Some(x) => vec![x],
+        None => IMPORTERS.to_vec(),
+    };
+
+    let mut offset = 0;
+    for (i, line) in content.split_inclusive('\n').take(MAX_PREAMBLE).enumerate() {
+        offset += line.len();
+        let line = line.trim_start_matches('\u{feff}');
+
+        for &delimiter in DELIMITERS {
+            if !line.as_bytes().contains(&delimiter) {
+                continue;
+            }
+            let headers = match parse_line(line, delimiter) {
+                Some(x) => x,
+                None => continue,
+            };
+
+            if let Some(importer) = candidates.iter().find(|x| x.detect(&headers)) {
+                return Ok(Sniffed {
+                    importer: *importer,
+                    delimiter,
+                    headers,
+                    line: i as u64 + 1,
+                    body: &content[offset..],
+                });
+            }
+        }
+    }
+
+    Err(ImportError::UnknownFormat)
+}
+
+/// Describes a failed row by column name instead of csv's positions, which are relative to the body
+fn describe(error: &csv::Error, headers: &StringRecord) -> String {
+    match error.kind() {
+        csv::ErrorKind::Deserialize { err, .. } => {
+            let column = err.field().and_then(|x| headers.get(x as usize));
+            match column {
+                Some(column) => format!("{}: {}", column, err.kind()),
+                None => err.kind().to_string(),
+            }
+        }
+        _ => error.to_string(),
+    }
+}
+
+/// A row of the export that could not be read
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RowError {
+    pub line: u64,
+    pub raw: String,
+    pub error: String,
+    /// For entries of documents
+    #[serde(default)]
+    pub shared: Shared,
+}
+
+/// An uploaded export, with the rows that could be read and the ones that could not
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Import {
+    pub id: String,
+    pub bank: String,
+    pub delimiter: u8,
+    /// The header row, empty for documents
+    pub headers: Vec<String>,
+    pub statements: Vec<Statement>,
+    pub errors: Vec<RowError>,
+}
+
+impl Import {
+    /// Reads a hand fixed row again, on success the row joins the other statements
+    pub fn retry(&mut self, line: u64, raw: &str) -> Result<(), String> {
+        if let Some(importer) = document_by_name(&self.bank) {
+            let shared = self
+                .errors
+                .iter()
+                .find(|x| x.line == line)
+                .map(|x| x.shared.clone())
+                .unwrap_or_default();
+            let result = importer.read(raw, &shared);
+            return self.retried(line, raw, result);
+        }
+
+        let importer =
+            by_name(&self.bank).ok_or_else(|| format!("Unknown bank '{}'.", self.bank))?;
+        let headers = StringRecord::from(self.headers.clone());
+        let result = parse_line(raw, self.delimiter)
+            .ok_or_else(|| "Empty row.".to_string())
+            .and_then(|record| importer.read(&record, &headers).map_err(|e| e.to_string()));
+        self.retried(line, raw, result)
+    }
+
+    fn retried(
+        &mut self,
+        line: u64,
+        raw: &str,
+        result: Result<Statement, String>,
+    ) -> Result<(), String> {
+        let index = self
+            .errors
+            .iter()
+            .position(|x| x.line == line)
+            .ok_or_else(|| format!("No failed row on line {}.", line))?;
+
+        match result {
+            Ok(statement) => {
+                self.errors.remove(index);
+                self.statements.push(statement);
+                Ok(())
+            }
+            Err(e) => {
+                let row = &mut self.errors[index];
+                row.raw = raw.to_string();
+                row.error = e.clone();
+                Err(e)
+            }
+        }
+    }
+
+    pub fn skip(&mut self, line: u64) {
+        self.errors.retain(|x| x.line != line);
+    }
+}
+
+/// Reads a complete export, detecting the bank unless `bank` is given.
+///
+/// Rows that fail to parse don't fail the import, they are collected in `Import::errors`.
+pub fn read(content: &str, bank: Option<&str>) -> Result<Import, ImportError> {
+    let document = match bank {
+        Some(name) => document_by_name(name),
+        None => DOCUMENT_IMPORTERS
+            .iter()
+            .find(|x| x.detect(content))
+            .copied(),
+    };
+    if let Some(importer) = document {
+        return read_document(content, importer);
+    }
+
+    let forced = match bank {
+        Some(name) => Some(by_name(name).ok_or_else(|| ImportError::UnknownBank(name.into()))?),
+        None => None,
+    };
+
+    let sniffed = sniff(content, forced)?;
+    let body = sniffed.body;
+
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .delimiter(sniffed.delimiter)
+        .from_reader(body.as_bytes());
+
+    let mut statements = Vec::new();
+    let mut errors = Vec::new();
+    let mut record = StringRecord::new();
+    loop {
+        let start = rdr.position().byte() as usize;
+        let line = sniffed.line + rdr.position().line();
+        let result = rdr.read_record(&mut record);
+        let end = rdr.position().byte() as usize;
+        let raw = body.get(start..end).unwrap_or_default().trim();
+
+        let result = match result {
+            Ok(false) => break,
+            Ok(true) if raw.is_empty() => continue,
+            Ok(true) => sniffed.importer.read(&record, &sniffed.headers),
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(statement) => statements.push(statement),
+            Err(e) => errors.push(RowError {
+                line,
+                raw: raw.to_string(),
+                error: describe(&e, &sniffed.headers),
+                shared: Shared::default(),
+            }),
+        }
+    }
+
+    Ok(Import {
+        id: uuid::Uuid::new_v4().to_string(),
+        bank: sniffed.importer.name().to_string(),
+        delimiter: sniffed.delimiter,
+        headers: sniffed.headers.iter().map(String::from).collect(),
+        statements,
+        errors,
+    })
+}
+
+/// Reads a complete document, entries that fail to parse are collected in `Import::errors`
+fn read_document(
+    content: &str,
+    importer: &'static dyn DocumentImporter,
+) -> Result<Import, ImportError> {
+    let mut statements = Vec::new();
+    let mut errors = Vec::new();
+    for (entry, statement) in importer.read_all(content)? {
+        match statement {
+            Ok(statement) => statements.push(statement),
+            Err(error) => errors.push(RowError {
+                line: entry.line,
+                raw: entry.raw,
+                error,
+                shared: entry.shared,
+            }),
+        }
+    }
+
+    Ok(Import {
+        id: uuid::Uuid::new_v4().to_string(),
+        bank: importer.name().to_string(),
+        delimiter: 0,
+        headers: Vec::new(),
+        statements,
+        errors,
+    })
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use regex::Regex;
+
+use super::super::{money, Note, Statement};
+use super::{decimal_cents, line_at, DocumentImporter, Entry, ImportError, Shared};
+
+lazy_static! {
+    /// OFX 1 is SGML and leaves most elements open, OFX 2 closes them, both start the same
+    static ref ELEMENT: Regex = Regex::new(r"<([A-Za-z0-9.]+)>([^<\r\n]*)").unwrap();
+    static ref TRANSACTION: Regex = Regex::new(r"(?is)<STMTTRN>.*?</STMTTRN>").unwrap();
+    static ref CURDEF: Regex = Regex::new(r"(?i)<CURDEF>\s*([A-Za-z]{3})").unwrap();
+    /// A statement of one account, bank and credit card statements look the same
+    static ref STATEMENT: Regex = Regex::new(r"(?is)<(CC)?STMTRS>.*?</(CC)?STMTRS>").unwrap();
+    static ref LEDGERBAL: Regex =
+        Regex::new(r"(?is)<LEDGERBAL>.*?<BALAMT>\s*([^<\r\n]+)").unwrap();
+    static ref ACCTID: Regex =
+        Regex::new(r"(?is)<(?:BANK|CC)ACCTFROM>.*?<ACCTID>\s*([^<\r\n]+)").unwrap();
+}
+
+/// Open Financial Exchange, every `STMTTRN` is a statement
+pub struct Ofx;
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// `20230103`, optionally followed by a time and a timezone
+fn date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok()
+}
+
+impl DocumentImporter for Ofx {
+    fn name(&self) -> &'static str {
+        "ofx"
+    }
+
+    fn detect(&self, content: &str) -> bool {
+        content.contains("OFXHEADER") || content.contains("<OFX>")
+    }
+
+    fn split(&self, content: &str) -> Result<Vec<Entry>, ImportError> {
+        let mut entries = Vec::new();
+        for statement in STATEMENT.find_iter(content) {
+            let text = statement.as_str();
+            let shared = Shared {
+                currency: CURDEF.captures(text).map(|x| x[1].to_uppercase()),
+                account: ACCTID.captures(text).map(|x| unescape(x[1].trim())),
+                balance: None,
+            };
+
+            let start = entries.len();
+            entries.extend(TRANSACTION.find_iter(text).map(|x| Entry {
+                line: line_at(content, statement.start() + x.start()),
+                raw: x.as_str().to_string(),
+                shared: shared.clone(),
+            }));
+            // The ledger balance is the balance after the last transaction of the statement
+            if let Some(last) = entries[start..].last_mut() {
+                last.shared.balance = LEDGERBAL
+                    .captures(text)
+                    .and_then(|x| decimal_cents(x[1].trim()).ok());
+            }
+        }
+
+        if entries.is_empty() && !content.to_uppercase().contains("<BANKTRANLIST>") {
+            return Err(ImportError::Document(
+                self.name(),
+                "No transaction list found.".to_string(),
+            ));
+        }
+
+        Ok(entries)
+    }
+
+    fn read(&self, entry: &str, shared: &Shared) -> Result<Statement, String> {
+        // The first occurrence wins, the ACCTID of BANKACCTTO is the only one in a transaction
+        let mut elements: HashMap<String, String> = HashMap::new();
+        for element in ELEMENT.captures_iter(entry) {
+            let value = unescape(element[2].trim());
+            if !value.is_empty() {
+                elements.entry(element[1].to_uppercase()).or_insert(value);
+            }
+        }
+        let get = |name: &str| elements.get(name).cloned();
+
+        let cents = decimal_cents(&get("TRNAMT").ok_or("STMTTRN without TRNAMT.")?)?;
+        let posted = get("DTPOSTED")
+            .as_deref()
+            .and_then(date)
+            .ok_or("STMTTRN without valid DTPOSTED.")?;
+
+        Ok(Statement {
+            currency: get("CURRENCY")
+                .or_else(|| get("CURSYM"))
+                .or_else(|| shared.currency.clone())
+                .unwrap_or_else(money::default_currency)
+                .to_uppercase(),
+            value_date: get("DTAVAIL").as_deref().and_then(date),
+            own_account: shared.account.clone(),
+            balance: shared.balance,
+            reference: get("FITID"),
+            counter_account: get("ACCTID"),
+            tegenpartij: get("NAME").or_else(|| get("PAYEEID")),
+            note: Note::from_text(get("MEMO")),
+            ..Statement::new(posted, cents)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::super::read;
+
+    /// OFX 1 leaves the elements open, the last transaction has no amount
+    const DOCUMENT: &str = "OFXHEADER:100
+DATA:OFXSGML
+
+<OFX>
+<BANKMSGSRSV1><STMTTRNRS><STMTRS>
+<CURDEF>EUR
+<BANKACCTFROM><BANKID>KREDBEBB<ACCTID>BE68539007547034<ACCTTYPE>CHECKING</BANKACCTFROM>
+<BANKTRANLIST>
+<STMTTRN>
+<TRNTYPE>DEBIT
+<DTPOSTED>20230103120000[+1:CET]
+<DTAVAIL>20230102
+<TRNAMT>-12.50
+<FITID>2023-0001
+<NAME>Colruyt &amp; Co
+<MEMO>+++090/9337/55493+++
+</STMTTRN>
+<STMTTRN>
+<TRNTYPE>CREDIT
+<DTPOSTED>20230104
+<TRNAMT>1250
+<CURRENCY>USD
+<FITID>2023-0002
+<NAME>Employer
+<BANKACCTTO><ACCTID>BE71096123456769</BANKACCTTO>
+</STMTTRN>
+<STMTTRN>
+<TRNTYPE>DEBIT
+<DTPOSTED>20230105
+<FITID>2023-0003
+</STMTTRN>
+</BANKTRANLIST>
+<LEDGERBAL><BALAMT>1237.50<DTASOF>20230105</LEDGERBAL>
+</STMTRS></STMTTRNRS></BANKMSGSRSV1>
+</OFX>
+";
+
+    #[test]
+    fn read_transactions() {
+        let import = read(DOCUMENT, None).unwrap();
+        assert_eq!(import.bank, "ofx");
+        assert_eq!(import.statements.len(), 2);
+
+        let paid = &import.statements[0];
+        assert_eq!(paid.amount, -1250);
+        assert_eq!(paid.currency, "EUR");
+        assert_eq!(paid.date, NaiveDate::from_ymd_opt(2023, 1, 3).unwrap());
+        assert_eq!(paid.value_date, NaiveDate::from_ymd_opt(2023, 1, 2));
+        assert_eq!(paid.reference.as_deref(), Some("2023-0001"));
+        assert_eq!(paid.tegenpartij.as_deref(), Some("Colruyt & Co"));
+        assert_eq!(paid.own_account.as_deref(), Some("BE68539007547034"));
+        assert_eq!(paid.ogm().as_deref(), Some("090933755493"));
+        assert_eq!(paid.counter_account, None);
+
+        let received = &import.statements[1];
+        assert_eq!(received.amount, 125000);
+        assert_eq!(received.currency, "USD");
+        assert_eq!(
+            received.counter_account.as_deref(),
+            Some("BE71096123456769")
+        );
+
+        assert_eq!(import.errors.len(), 1);
+        assert_eq!(import.errors[0].line, 27);
+        assert_eq!(import.errors[0].error, "STMTTRN without TRNAMT.");
+        // The ledger balance belongs to the last transaction, even when it failed
+        assert_eq!(import.errors[0].shared.balance, Some(123750));
+    }
+
+    #[test]
+    fn empty_document() {
+        assert!(read("<OFX></OFX>", None).is_err());
+        let empty = "<OFX><STMTRS><BANKTRANLIST></BANKTRANLIST></STMTRS></OFX>";
+        assert!(read(empty, None).unwrap().statements.is_empty());
+    }
+}
@@ -0,0 +1,161 @@
+use chrono::NaiveDate;
+
+use super::super::{money, Note, Statement};
+use super::{line_at, DocumentImporter, Entry, ImportError, Shared};
+
+/// Quicken Interchange Format, records end with a `^` line
+pub struct Qif;
+
+/// Day first like the banks here, month first only when the day can't be a month
+const DATE_FORMATS: &[&str] = &[
+    "%d/%m/%Y", "%d-%m-%Y", "%d.%m.%Y", "%Y-%m-%d", "%d/%m/%y", "%m/%d/%Y", "%m/%d/%y",
+];
+
+/// Quicken writes `1/ 3'23` for `1/3/2023`
+fn date(value: &str) -> Option<NaiveDate> {
+    let value = value.replace(' ', "");
+    let value = match value.split_once('\'') {
+        Some((day_month, year)) if year.len() == 2 => format!("{}/20{}", day_month, year),
+        Some((day_month, year)) => format!("{}/{}", day_month, year),
+        None => value,
+    };
+    DATE_FORMATS
+        .iter()
+        .find_map(|f| NaiveDate::parse_from_str(&value, f).ok())
+}
+
+impl DocumentImporter for Qif {
+    fn name(&self) -> &'static str {
+        "qif"
+    }
+
+    fn detect(&self, content: &str) -> bool {
+        content
+            .trim_start_matches('\u{feff}')
+            .trim_start()
+            .starts_with("!Type:")
+    }
+
+    fn split(&self, content: &str) -> Result<Vec<Entry>, ImportError> {
+        let mut entries = Vec::new();
+        let mut start = None;
+        let mut offset = 0;
+        // Records after `!Account` describe accounts, they are no transactions
+        let mut account = false;
+
+        for line in content.split_inclusive('\n') {
+            let trimmed = line.trim();
+            if trimmed == "^" {
+                if let (Some(start), false) = (start.take(), account) {
+                    entries.push(Entry {
+                        line: line_at(content, start),
+                        raw: content[start..offset].trim().to_string(),
+                        shared: Shared::default(),
+                    });
+                }
+            } else if trimmed.starts_with('!') {
+                // Headers like `!Type:Bank` and `!Account` are not part of a record
+                start = None;
+                account = trimmed.eq_ignore_ascii_case("!Account");
+            } else if !trimmed.is_empty() && start.is_none() {
+                start = Some(offset);
+            }
+            offset += line.len();
+        }
+
+        if let Some(start) = start {
+            return Err(ImportError::Document(
+                self.name(),
+                format!(
+                    "Record on line {} does not end with '^'.",
+                    line_at(content, start)
+                ),
+            ));
+        }
+
+        Ok(entries)
+    }
+
+    fn read(&self, entry: &str, _shared: &Shared) -> Result<Statement, String> {
+        let field = |code: char| {
+            entry
+                .lines()
+                .map(str::trim)
+                .find(|x| x.starts_with(code))
+                .map(|x| x[code.len_utf8()..].trim().to_string())
+                .filter(|x| !x.is_empty())
+        };
+
+        let amount = field('T')
+            .or_else(|| field('U'))
+            .ok_or("Record without amount.")?;
+        let cents = money::parse_cents(&amount).map_err(|e| e.to_string())?;
+        let date = field('D').ok_or("Record without date.")?;
+        let date = self::date(&date).ok_or_else(|| format!("Invalid date '{}'.", date))?;
+
+        Ok(Statement {
+            reference: field('N'),
+            tegenpartij: field('P'),
+            note: Note::from_text(field('M')),
+            ..Statement::new(date, cents)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::super::read;
+    use super::date;
+
+    #[test]
+    fn read_dates() {
+        let day = |y, m, d| NaiveDate::from_ymd_opt(y, m, d);
+        assert_eq!(date("03/01/2023"), day(2023, 1, 3));
+        assert_eq!(date("1/ 3'23"), day(2023, 3, 1));
+        assert_eq!(date("1/3'2023"), day(2023, 3, 1));
+        assert_eq!(date("2023-01-03"), day(2023, 1, 3));
+        assert_eq!(date("12/31/2023"), day(2023, 12, 31));
+        assert_eq!(date("31/31/2023"), None);
+    }
+
+    #[test]
+    fn read_records() {
+        let content = "!Type:Bank
+D03/01/2023
+T-1.234,50
+PColruyt
+M+++090/9337/55493+++
+N42
+^
+D04/01/2023
+PNo amount
+^
+!Account
+NSavings
+TBank
+^
+!Type:Bank
+D05/01/2023
+U25.00
+^
+";
+        let import = read(content, None).unwrap();
+        assert_eq!(import.bank, "qif");
+        assert_eq!(import.statements.len(), 2);
+
+        let paid = &import.statements[0];
+        assert_eq!(paid.amount, -123450);
+        assert_eq!(paid.tegenpartij.as_deref(), Some("Colruyt"));
+        assert_eq!(paid.reference.as_deref(), Some("42"));
+        assert_eq!(paid.ogm().as_deref(), Some("090933755493"));
+        assert_eq!(import.statements[1].amount, 2500);
+
+        assert_eq!(import.errors.len(), 1);
+        assert_eq!(import.errors[0].line, 8);
+        assert_eq!(import.errors[0].error, "Record without amount.");
+
+        assert!(read("!Type:Bank\nD03/01/2023\nT1\n", None).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+use rocket::serde::{Deserialize, Serialize};
+
+use super::{ogm, Scan};
+use crate::repository::Repository;
+
+/// An invoice we sent or a payment we expect, it is paid with its structured communication
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Invoice {
+    pub id: String,
+    /// The twelve digits
+    pub ogm: String,
+    /// In cents, any amount pays the invoice when it is not known
+    pub amount: Option<isize>,
+    /// Where the payment is booked, like `Assets:Receivables` or an income account
+    pub account: String,
+    pub description: String,
+    /// The statement that paid it in a posted scan, paid invoices are not matched again
+    #[serde(default)]
+    pub paid_by: Option<String>,
+}
+
+pub type Invoices = Repository<Vec<Invoice>>;
+
+impl Invoice {
+    pub fn new(
+        communication: &str,
+        amount: Option<isize>,
+        account: &str,
+        description: &str,
+    ) -> Result<Self, String> {
+        let digits = ogm::parse(communication)
+            .filter(|x| ogm::is_valid(x))
+            .ok_or_else(|| {
+                format!(
+                    "'{}' is not a valid structured communication.",
+                    communication
+                )
+            })?;
+        if amount.map(|x| x <= 0).unwrap_or(false) {
+            return Err(
+                "An invoice is paid with incoming money, its amount must be positive.".to_string(),
+            );
+        }
+        if account.is_empty() {
+            return Err("An invoice needs an account.".to_string());
+        }
+
+        Ok(Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            ogm: digits,
+            amount,
+            account: account.to_string(),
+            description: description.to_string(),
+            paid_by: None,
+        })
+    }
+
+    /// Not paid in a posted scan and not `matched` by a statement of a pending one
+    pub fn is_open(&self, matched: &[String]) -> bool {
+        self.paid_by.is_none() && !matched.contains(&self.id)
+    }
+}
+
+/// The invoices paid by the statements of pending scans, deleted statements pay nothing.
+/// Undoing a match or dropping the scan opens the invoice again.
+pub fn matched(scans: &[Scan]) -> Vec<String> {
+    scans
+        .iter()
+        .flat_map(|scan| scan.grouped.iter())
+        .filter(|x| x.key != "deleted")
+        .flat_map(|x| x.statements.iter())
+        .filter_map(|x| x.invoice_id.clone())
+        .collect()
+}
+
+/// Marks the invoices the statements of a posted scan pay as paid
+pub fn settle(invoices: &mut [Invoice], scan: &Scan) {
+    for statement in scan
+        .grouped
+        .iter()
+        .filter(|x| x.key != "deleted")
+        .flat_map(|x| x.statements.iter())
+    {
+        let invoice = statement
+            .invoice_id
+            .as_ref()
+            .and_then(|id| invoices.iter_mut().find(|x| &x.id == id));
+        if let Some(invoice) = invoice {
+            invoice.paid_by = Some(statement.id.0.clone());
+        }
+    }
+}
+
+impl Scan {
+    /// Books every incoming statement that pays an open invoice on the account of the invoice.
+    /// Invoices in `matched` are paid by other pending statements. Returns the number of paid
+    /// invoices.
+    pub fn match_invoices(&mut self, invoices: &[Invoice], matched: &[String]) -> usize {
+        let mut found = Vec::new();
+        let statements = self
+            .grouped
+            .iter()
+            .filter(|x| x.key != "deleted")
+            .flat_map(|g| g.statements.iter().map(move |x| (g, x)))
+            .filter(|(_, x)| x.amount > 0 && x.duplicate.is_none() && x.invoice_id.is_none());
+
+        for (group, statement) in statements {
+            let digits = match statement.ogm() {
+                Some(x) => x,
+                None => continue,
+            };
+            let invoice = invoices.iter().position(|x| {
+                x.is_open(matched)
+                    && x.ogm == digits
+                    && x.amount.map(|a| a == statement.amount).unwrap_or(true)
+                    && !found.iter().any(|(_, _, i)| i == &x.id)
+            });
+            if let Some(index) = invoice {
+                let invoice = &invoices[index];
+                found.push((
+                    group.key.clone(),
+                    statement.id.0.clone(),
+                    invoice.id.clone(),
+                ));
+            }
+        }
+
+        for (group, item, invoice) in found.iter() {
+            let invoice = match invoices.iter().find(|x| &x.id == invoice) {
+                Some(x) => x,
+                None => continue,
+            };
+            if let Some(statement) = self.statement_mut(group, item) {
+                statement.invoice = Some(invoice.description.clone());
+                statement.invoice_id = Some(invoice.id.clone());
+            }
+            let to = format!("Invoices on {}", invoice.account);
+            self.book(group, item, &to, &invoice.account);
+        }
+
+        found.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::super::{GroupingStrategy, Note, Statement};
+    use super::*;
+
+    fn payment(cents: isize, ogm: &str) -> Statement {
+        let mut statement = Statement::new(NaiveDate::from_ymd_opt(2023, 1, 3).unwrap(), cents);
+        statement.note = Note {
+            structured: Some(ogm.to_string()),
+            free: None,
+        };
+        statement
+    }
+
+    fn scan(statements: Vec<Statement>) -> Scan {
+        Scan::new(statements, &[], &[], GroupingStrategy::default())
+    }
+
+    #[test]
+    fn new_invoice() {
+        assert!(Invoice::new("+++090/9337/55493+++", Some(4000), "Income:Rent", "").is_ok());
+        assert!(Invoice::new("090933755493", None, "Income:Rent", "").is_ok());
+        assert!(Invoice::new("+++090/9337/55494+++", None, "Income:Rent", "").is_err());
+        assert!(Invoice::new("090933755493", Some(-4000), "Income:Rent", "").is_err());
+        assert!(Invoice::new("090933755493", Some(0), "Income:Rent", "").is_err());
+        assert!(Invoice::new("090933755493", None, "", "").is_err());
+    }
+
+    #[test]
+    fn paid_with_the_scan() {
+        let rent = Invoice::new("+++090/9337/55493+++", Some(4000), "Income:Rent", "rent").unwrap();
+        let any = Invoice::new("123456789002", None, "Income:Other", "other").unwrap();
+        let mut invoices = vec![rent, any];
+
+        let mut scans = vec![scan(vec![
+            payment(3999, "+++090/9337/55493+++"),
+            payment(4000, "090/9337/55493"),
+            payment(4000, "090/9337/55493"),
+            payment(-4000, "123456789002"),
+        ])];
+        let count = scans[0].edit("Match invoices", |scan| scan.match_invoices(&invoices, &[]));
+        assert_eq!(count, 1);
+        let matched = super::matched(&scans);
+        assert_eq!(matched, vec![invoices[0].id.clone()]);
+        assert!(!invoices[0].is_open(&matched));
+        assert!(invoices[1].is_open(&matched));
+
+        // A second scan can't pay the same invoice again
+        scans.push(scan(vec![payment(4000, "090933755493")]));
+        assert_eq!(scans[1].match_invoices(&invoices, &matched), 0);
+
+        // Undoing the match opens the invoice again, so does deleting the payment
+        scans[0].undo();
+        assert!(super::matched(&scans).is_empty());
+        scans[0].redo();
+        let (group, item) = scans[0]
+            .grouped
+            .iter()
+            .flat_map(|g| g.statements.iter().map(move |x| (g, x)))
+            .find(|(_, x)| x.invoice_id.is_some())
+            .map(|(g, x)| (g.key.clone(), x.id.0.clone()))
+            .unwrap();
+        assert_eq!(group, "Invoices on Income:Rent");
+        scans[0].delete_item(&group, &item);
+        assert!(super::matched(&scans).is_empty());
+
+        // Only posting the scan pays it for good
+        scans[0].restore(&item, None);
+        settle(&mut invoices, &scans[0]);
+        assert_eq!(invoices[0].paid_by, Some(item));
+        assert!(!invoices[0].is_open(&[]));
+    }
+}
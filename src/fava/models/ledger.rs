@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::process::Command;
+
+use chrono::NaiveDate;
+use regex::Regex;
+
+lazy_static! {
+    static ref HEADER: Regex =
+        Regex::new(r"^(\d{4}-\d{2}-\d{2})\s+(\*|!|txn)(\s+.*)?$").unwrap();
+    static ref STRING: Regex = Regex::new(r#""((?:[^"\\]|\\.)*)""#).unwrap();
+    static ref META: Regex = Regex::new(r"^\s+([a-z][a-zA-Z0-9_-]*):\s*(.*)$").unwrap();
+    static ref POSTING: Regex = Regex::new(
+        r"^\s+(?:[!*]\s+)?([A-Z][A-Za-z0-9-]*(?::[A-Za-z0-9][A-Za-z0-9-]*)+)(?:\s+(-?[0-9][0-9,]*(?:\.[0-9]*)?)\s+([A-Z][A-Z0-9'._-]*))?"
+    )
+    .unwrap();
+}
+
+/// A posting of a transaction, elided amounts are `None`
+#[derive(Debug, Clone)]
+pub struct Posting {
+    pub account: String,
+    pub amount: Option<isize>,
+    pub currency: Option<String>,
+}
+
+/// A transaction as found in a beancount file, only the parts ingest cares about
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    pub date: NaiveDate,
+    pub payee: Option<String>,
+    pub narration: String,
+    pub meta: HashMap<String, String>,
+    pub postings: Vec<Posting>,
+}
+
+/// Parses a beancount number (`-1,234.56`) into cents
+fn parse_number(number: &str) -> Option<isize> {
+    let number = number.replace(',', "");
+    let (whole, fraction) = number.split_once('.').unwrap_or((&number, ""));
+    let fraction: String = fraction.chars().chain("00".chars()).take(2).collect();
+    let negative = whole.starts_with('-');
+    let cents = whole.trim_start_matches('-').parse::<isize>().ok()? * 100
+        + fraction.parse::<isize>().ok()?;
+    Some(if negative { -cents } else { cents })
+}
+
+fn unquote(value: &str) -> String {
+    STRING
+        .captures(value)
+        .map(|x| x[1].to_string())
+        .unwrap_or_else(|| value.trim().to_string())
+}
+
+/// Reads all transactions, other directives are skipped
+pub fn parse(content: &str) -> Vec<Transaction> {
+    let mut out: Vec<Transaction> = Vec::new();
+    let mut current: Option<Transaction> = None;
+
+    for line in content.lines() {
+        let line = line.split(" ;").next().unwrap_or(line);
+
+        if let Some(tx) = current.as_mut() {
+            if line.starts_with(char::is_whitespace) && !line.trim().is_empty() {
+                if let Some(meta) = META.captures(line) {
+                    // Posting metadata is merged with the transaction metadata
+                    tx.meta.insert(meta[1].to_string(), unquote(&meta[2]));
+                } else if let Some(posting) = POSTING.captures(line) {
+                    tx.postings.push(Posting {
+                        account: posting[1].to_string(),
+                        amount: posting.get(2).and_then(|x| parse_number(x.as_str())),
+                        currency: posting.get(3).map(|x| x.as_str().to_string()),
+                    });
+                }
+                continue;
+            }
+            out.extend(current.take());
+        }
+
+        if let Some(header) = HEADER.captures(line) {
+            let date = match NaiveDate::parse_from_str(&header[1], "%Y-%m-%d") {
+                Ok(x) => x,
+                Err(_) => continue,
+            };
+            let rest = header.get(3).map(|x| x.as_str()).unwrap_or("");
+            let mut strings: Vec<_> = STRING
+                .captures_iter(rest)
+                .map(|x| x[1].to_string())
+                .collect();
+
+            let narration = strings.pop().unwrap_or_default();
+            let payee = strings.pop();
+
+            current = Some(Transaction {
+                date,
+                payee,
+                narration,
+                meta: HashMap::new(),
+                postings: Vec::new(),
+            });
+        }
+    }
+    out.extend(current);
+
+    out
+}
+
+/// Reads the transactions of a beancount file, a missing file has no transactions
+pub fn read(location: &str) -> Vec<Transaction> {
+    fs::read_to_string(location)
+        .map(|x| parse(&x))
+        .unwrap_or_default()
+}
+
+lazy_static! {
+    static ref OPEN: Regex = Regex::new(
+        r"^\d{4}-\d{2}-\d{2}\s+open\s+([A-Z][A-Za-z0-9-]*(?::[A-Za-z0-9][A-Za-z0-9-]*)+)"
+    )
+    .unwrap();
+    static ref TAGS: Regex =
+        Regex::new(r#"^(?:\s*"(?:[^"\\]|\\.)*")*(?:\s+[#^][A-Za-z0-9_/.-]+)*\s*$"#).unwrap();
+    static ref PRICE: Regex =
+        Regex::new(r"@@\s+(-?[0-9][0-9,]*(?:\.[0-9]*)?)\s+([A-Z][A-Z0-9'._-]*)\s*$").unwrap();
+    static ref BALANCE: Regex = Regex::new(
+        r"^\d{4}-\d{2}-\d{2}\s+balance\s+([A-Z][A-Za-z0-9-]*(?::[A-Za-z0-9][A-Za-z0-9-]*)+)\s+-?[0-9][0-9,]*(?:\.[0-9]*)?\s+[A-Z][A-Z0-9'._-]*\s*$"
+    )
+    .unwrap();
+}
+
+/// The accounts `content` opens
+pub fn opened(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|x| OPEN.captures(x).map(|x| x[1].to_string()))
+        .collect()
+}
+
+/// The accounts a beancount file opens, a missing file opens none
+pub fn read_opened(location: &str) -> Vec<String> {
+    fs::read_to_string(location)
+        .map(|x| opened(&x))
+        .unwrap_or_default()
+}
+
+/// The line a transaction starts on, its weight per currency and its postings without amount
+type Open = Option<(usize, HashMap<String, isize>, usize)>;
+
+fn close(current: &mut Open, errors: &mut Vec<String>) {
+    if let Some((line, weights, elided)) = current.take() {
+        if elided > 1 {
+            errors.push(format!(
+                "Line {}: more than one posting without amount.",
+                line
+            ));
+        } else if elided == 0 {
+            for (currency, cents) in weights.into_iter().filter(|(_, x)| *x != 0) {
+                errors.push(format!(
+                    "Line {}: transaction does not balance, {:.2} {} left.",
+                    line,
+                    cents as f64 / 100.0,
+                    currency
+                ));
+            }
+        }
+    }
+}
+
+/// Checks text that is about to be added to `existing` without the help of bean-check.
+///
+/// Every line has to be understood, transactions have to balance and the accounts have to be
+/// opened, when `existing` opens accounts at all. Besides transactions, `open` and `balance`
+/// directives are allowed.
+pub fn check(existing: &str, addition: &str) -> Vec<String> {
+    let mut opened = opened(existing);
+    opened.extend(self::opened(addition));
+
+    let mut errors = Vec::new();
+    let mut current: Open = None;
+
+    for (index, line) in addition.lines().enumerate() {
+        let number = index + 1;
+        let line = line.split(" ;").next().unwrap_or(line);
+        if line.trim().is_empty() {
+            close(&mut current, &mut errors);
+            continue;
+        }
+
+        if !line.starts_with(char::is_whitespace) {
+            close(&mut current, &mut errors);
+            if OPEN.is_match(line) {
+                continue;
+            }
+            if let Some(balance) = BALANCE.captures(line) {
+                if !opened.is_empty() && !opened.iter().any(|x| *x == balance[1]) {
+                    errors.push(format!(
+                        "Line {}: account {} is not opened.",
+                        number, &balance[1]
+                    ));
+                }
+                continue;
+            }
+            match HEADER.captures(line) {
+                Some(header)
+                    if NaiveDate::parse_from_str(&header[1], "%Y-%m-%d").is_ok()
+                        && TAGS.is_match(header.get(3).map(|x| x.as_str()).unwrap_or("")) =>
+                {
+                    current = Some((number, HashMap::new(), 0));
+                }
+                _ => errors.push(format!("Line {}: not a transaction: '{}'.", number, line)),
+            }
+            continue;
+        }
+
+        let (_, weights, elided) = match current.as_mut() {
+            Some(x) => x,
+            None => {
+                errors.push(format!(
+                    "Line {}: indented line outside a transaction.",
+                    number
+                ));
+                continue;
+            }
+        };
+
+        if META.is_match(line) {
+            continue;
+        }
+
+        let posting = match POSTING.captures(line) {
+            Some(x) if x[0].len() == line.len() || PRICE.is_match(&line[x[0].len()..]) => x,
+            _ => {
+                errors.push(format!(
+                    "Line {}: not a posting: '{}'.",
+                    number,
+                    line.trim()
+                ));
+                continue;
+            }
+        };
+
+        if !opened.is_empty() && !opened.iter().any(|x| *x == posting[1]) {
+            errors.push(format!(
+                "Line {}: account {} is not opened.",
+                number, &posting[1]
+            ));
+        }
+
+        let amount = posting.get(2).and_then(|x| parse_number(x.as_str()));
+        match (amount, posting.get(3)) {
+            (Some(amount), Some(currency)) => {
+                // A total price replaces the weight of the posting
+                let price = PRICE
+                    .captures(&line[posting[0].len()..])
+                    .and_then(|x| Some((parse_number(&x[1])?, x[2].to_string())));
+                let (cents, currency) = match price {
+                    Some((cents, price_currency)) => {
+                        (amount.signum() * cents.abs(), price_currency)
+                    }
+                    None => (amount, currency.as_str().to_string()),
+                };
+                *weights.entry(currency).or_default() += cents;
+            }
+            _ => *elided += 1,
+        }
+    }
+    close(&mut current, &mut errors);
+
+    errors
+}
+
+/// Validates the ledger at `path` with bean-check, `None` when bean-check can't be run
+fn bean_check(command: &str, path: &str) -> Option<Vec<String>> {
+    let output = Command::new(command).arg(path).output().ok()?;
+    if output.status.success() {
+        return Some(Vec::new());
+    }
+
+    let errors: Vec<_> = String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .chain(String::from_utf8_lossy(&output.stdout).lines())
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(String::from)
+        .collect();
+    if errors.is_empty() {
+        Some(vec![format!("{} failed with {}.", command, output.status)])
+    } else {
+        Some(errors)
+    }
+}
+
+/// Appends `addition` to the ledger at `location`.
+///
+/// The new ledger is written next to the old one and validated first, with `command` when it can
+/// be run and with [`check`] otherwise. Only then is the old ledger kept as a timestamped backup
+/// and replaced in one rename. Returns the backup, if there was a ledger to back up.
+pub fn append(
+    location: &str,
+    addition: &str,
+    command: &str,
+) -> Result<Option<String>, Vec<String>> {
+    let io_error = |e: std::io::Error| vec![format!("{}: {}", location, e)];
+
+    let existing = match fs::read_to_string(location) {
+        Ok(x) => Some(x),
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => return Err(io_error(e)),
+    };
+
+    let temp = format!("{}.new", location);
+    let content = format!("{}{}", existing.as_deref().unwrap_or(""), addition);
+    fs::write(&temp, content).map_err(io_error)?;
+
+    let errors = bean_check(command, &temp)
+        .unwrap_or_else(|| check(existing.as_deref().unwrap_or(""), addition));
+    if !errors.is_empty() {
+        let _ = fs::remove_file(&temp);
+        return Err(errors);
+    }
+
+    let backup = match existing {
+        Some(_) => {
+            let backup = format!(
+                "{}.{}.bak",
+                location,
+                chrono::Local::now().format("%Y%m%d-%H%M%S")
+            );
+            fs::copy(location, &backup).map_err(io_error)?;
+            Some(backup)
+        }
+        None => None,
+    };
+    fs::rename(&temp, location).map_err(io_error)?;
+
+    Ok(backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check;
+
+    #[test]
+    fn check_addition() {
+        let existing = "2020-01-01 open Assets:Bank\n2020-01-01 open Expenses:Food\n";
+        let ok = "\n2023-01-03 * \"Shop\" \"food\" #tag\n    import-id: \"1\"\n    Assets:Bank -12.50 EUR\n    Expenses:Food\n\n2023-01-04 * \"Shop\"\n    Assets:Bank -9.20 EUR\n    Expenses:Food 10.00 USD @@ 9.20 EUR\n";
+        assert!(check(existing, ok).is_empty());
+
+        let unbalanced =
+            "2023-01-03 * \"Shop\"\n    Assets:Bank -12.50 EUR\n    Expenses:Food 12.00 EUR\n";
+        assert_eq!(check(existing, unbalanced).len(), 1);
+
+        let unknown = "2023-01-03 * \"Shop\"\n    Assets:Bank -12.50 EUR\n    Expenses:Typo\n";
+        assert_eq!(check(existing, unknown).len(), 1);
+
+        assert_eq!(check(existing, "2023-01-03 Shop\n").len(), 1);
+
+        let directives = "2023-01-04 balance Assets:Bank 1189.56 EUR\n2023-01-04 open Assets:Cash\n2023-01-05 balance Assets:Cash 0.00 EUR\n";
+        assert!(check(existing, directives).is_empty());
+        assert_eq!(
+            check(existing, "2023-01-04 balance Assets:Typo 1.00 EUR\n").len(),
+            1
+        );
+    }
+}
@@ -1,124 +1,58 @@
-use std::{collections::HashMap, str::FromStr};
-
 use crate::repository::Repository;
 use chrono::NaiveDate;
-use regex::Regex;
 use rocket::serde::{Deserialize, Serialize};
 
+pub mod aliases;
+mod balance;
+mod description;
+mod duplicates;
+pub mod exporter;
+mod grouping;
+pub mod history;
+pub mod iban;
+pub mod importer;
+pub mod invoices;
+pub mod ledger;
+pub mod money;
 mod my_date;
-
-#[derive(Deserialize, Debug, Clone)]
-struct NoteUgly {
-    #[serde(rename = "gestructureerde mededeling")]
-    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
-    structured: Option<String>,
-    #[serde(rename = "Vrije mededeling")]
-    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
-    free: Option<String>,
-}
-
-impl NoteUgly {
-    fn into(self) -> Note {
-        let NoteUgly { structured, free } = self;
-        Note { structured, free }
-    }
-}
-
-#[derive(Deserialize, Debug, Clone)]
-pub struct StatementUgly {
-    #[serde(default)]
-    id: ID,
-    #[serde(default)]
-    category: Option<String>,
-    #[serde(rename = "Omschrijving")]
-    #[serde(deserialize_with = "my_date::deserialize_betaling")]
-    description: Result<Description, String>,
-    #[serde(rename = "Bedrag")]
-    amount: f32,
-    #[serde(rename = "Datum")]
-    #[serde(with = "my_date")]
-    date: NaiveDate,
-    #[serde(rename = "Naam tegenpartij")]
-    #[serde(deserialize_with = "my_date::deserialize_spacy_string")]
-    tegenpartij: Option<String>,
-    #[serde(flatten)]
-    note: NoteUgly,
-}
-
-impl StatementUgly {
-    pub fn into(self) -> Statement {
-        let StatementUgly {
-            id,
-            category,
-            description,
-            amount,
-            date,
-            tegenpartij,
-            note,
-        } = self;
-        let note = note.into();
-        Statement {
-            id,
-            category,
-            description: description.ok(),
-            amount: (amount * 100.0) as isize,
-            date,
-            tegenpartij,
-            note,
-        }
-    }
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
+pub mod ogm;
+pub mod output;
+pub mod own_accounts;
+pub mod rules;
+pub mod shared;
+pub mod split;
+pub mod suggest;
+pub mod transfers;
+mod trash;
+
+pub use description::Description;
+pub use grouping::GroupingStrategy;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 struct Note {
     structured: Option<String>,
     free: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct Description {
-    way: String,
-    label: String,
-    user: Option<String>,
-}
-
-impl FromStr for Description {
-    type Err = &'static str;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let re = Regex::new(r"[0-9]{2}-[0-9]{2}").unwrap();
-        let splits = re.splitn(s, 2).collect::<Vec<_>>();
-
-        if splits.len() < 2 {
-            return Err("Not enough splits!");
-        }
-
-        let way = splits[0].trim().to_string();
-        let label;
-        let mut user = None;
-        // Dom
-        if splits[0].contains("DOMICILIERING")
-            || splits[0].contains("DOORLOPENDE")
-            || splits[0].starts_with("OVERSCHRIJVING")
-        {
-            label = s
-                .split(':')
-                .nth(1)
-                .ok_or("No ':' in DOMICILIERING")?
-                .trim()
-                .to_string();
+impl Note {
+    /// Banks that only have one note column, use it as structured note when it looks like one
+    fn from_text(text: Option<String>) -> Self {
+        let is_structured = text
+            .as_deref()
+            .map(|x| x.starts_with("+++") || x.starts_with("***"))
+            .unwrap_or(false);
+
+        if is_structured {
+            Note {
+                structured: text,
+                free: None,
+            }
         } else {
-            let (_, s) = splits[1].split_once("UUR").ok_or("NO 'UUR' in betaling")?;
-            let (l, _) = s.split_once("MET").ok_or("NO 'MET' in betaling")?;
-            label = l.trim().to_string();
-            user = Some(
-                s.split(':')
-                    .last()
-                    .ok_or("NO ':' in betaling")?
-                    .trim()
-                    .to_string(),
-            );
+            Note {
+                structured: None,
+                free: text,
+            }
         }
-        Ok(Description { way, label, user })
     }
 }
 
@@ -130,41 +64,145 @@ impl Default for ID {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Statement {
     pub id: ID,
     pub category: Option<String>,
     description: Option<Description>,
     amount: isize,
+    #[serde(default = "money::default_currency")]
+    pub currency: String,
+    /// What the bank says is on the account after this statement, in cents
+    #[serde(default)]
+    pub balance: Option<isize>,
     #[serde(with = "my_date")]
     pub date: NaiveDate,
+    /// When the money counts for interest, when the bank tells
+    #[serde(default)]
+    pub value_date: Option<NaiveDate>,
     tegenpartij: Option<String>,
+    /// The canonical payee an alias gave this statement
+    #[serde(default)]
+    pub payee: Option<String>,
     note: Note,
+    /// The bank's own reference of the transaction
+    #[serde(default)]
+    pub reference: Option<String>,
+    /// The reference the payer gave the payment, it travels along to the receiving bank
+    #[serde(default)]
+    pub end_to_end: Option<String>,
+    /// Account number of the counterparty, as the bank wrote it
+    #[serde(default)]
+    pub counter_account: Option<String>,
+    /// Our own account the statement was booked on, as the bank wrote it
+    #[serde(default)]
+    pub own_account: Option<String>,
+    /// BIC of the bank of the counterparty
+    #[serde(default)]
+    pub bic: Option<String>,
+    /// Why this statement looks like one that is already known
+    #[serde(default)]
+    pub duplicate: Option<String>,
+    /// Parts of the amount that go to other accounts than `category`
+    #[serde(default)]
+    pub splits: Vec<split::Split>,
+    /// The group this statement was in before it was deleted
+    #[serde(default)]
+    pub deleted_from: Option<String>,
+    /// The description of the invoice this statement pays
+    #[serde(default)]
+    pub invoice: Option<String>,
+    /// The invoice this statement pays, it is paid for good once the scan is posted
+    #[serde(default)]
+    pub invoice_id: Option<String>,
+    /// The transfer between our accounts this statement is a half of. The incoming half is
+    /// deleted, the outgoing half books the transfer.
+    #[serde(default)]
+    pub transfer: Option<String>,
 }
 
 impl Statement {
+    pub fn new(date: NaiveDate, amount: isize) -> Self {
+        Self {
+            id: ID::default(),
+            category: None,
+            description: None,
+            amount,
+            currency: money::default_currency(),
+            balance: None,
+            date,
+            value_date: None,
+            tegenpartij: None,
+            payee: None,
+            note: Note::default(),
+            reference: None,
+            end_to_end: None,
+            counter_account: None,
+            own_account: None,
+            bic: None,
+            duplicate: None,
+            splits: Vec::new(),
+            deleted_from: None,
+            invoice: None,
+            invoice_id: None,
+            transfer: None,
+        }
+    }
+
     pub fn needs_categorised(&self) -> bool {
         self.category.is_none()
     }
-    pub fn to_output<'a, 'b>(&'b self, pay: &'a str) -> ScanOutput<'a, 'b> {
-        let name = self
-            .description
-            .as_ref()
-            .map(|x| x.label.as_str())
-            .or(self.tegenpartij.as_ref().map(String::as_str))
-            .unwrap_or("Nothing found :(");
+    pub fn to_output<'a, 'b>(
+        &'b self,
+        pay: &'a str,
+        config: &output::OutputConfig,
+    ) -> ScanOutput<'a, 'b> {
+        let narration = self.render(&config.narration);
+        let payee = Some(self.render(&config.payee)).filter(|x| !x.is_empty() && *x != narration);
+
+        let money = |cents| money::Money {
+            cents,
+            currency: self.currency.clone(),
+        };
+
+        // A converted payment keeps what was paid in the foreign currency, when it is not split
+        let remainder = self.remainder();
+        let price = self.price().filter(|_| self.splits.is_empty()).map(|x| {
+            let original = money::Money {
+                cents: -self.amount.signum() * x.cents.abs(),
+                currency: x.currency.clone(),
+            };
+            (original, money(self.amount.abs()))
+        });
 
         ScanOutput {
             date: &self.date,
             pay,
-            amount: self.amount as f32 / 100.0,
-            category: self.category.as_ref().unwrap().to_string(),
-            name: name.to_string(),
+            amount: money(self.amount),
+            splits: self
+                .split_amounts()
+                .map(|(account, amount)| (account, money(-amount)))
+                .collect(),
+            category: Some(self.category.as_ref().unwrap().to_string()).filter(|_| remainder != 0),
+            price,
+            payee,
+            narration,
+            metadata: self.metadata(config),
+            tags: self.tags(config),
+            links: self.links(config),
         }
     }
+
+    /// The amount in the currency it was paid in, when that is not the currency of the account
+    pub fn price(&self) -> Option<&money::Money> {
+        self.description
+            .as_ref()
+            .and_then(|x| x.original.as_ref())
+            .filter(|x| x.currency != self.currency)
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct GroupedStatement {
     pub key: String,
     pub statements: Vec<Statement>,
@@ -202,85 +240,176 @@ impl GroupedStatement {
 }
 
 pub type Scans = Repository<Vec<Scan>>;
+pub type Imports = Repository<Vec<importer::Import>>;
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Scan {
     pub id: String,
     pub grouped: Vec<GroupedStatement>,
+    #[serde(default)]
+    pub history: history::History,
+    /// The file in the watched import folder this scan was read from
+    #[serde(default)]
+    pub source: Option<String>,
 }
 
 #[allow(deprecated)]
 impl Scan {
-    pub fn new(items: Vec<Statement>) -> Self {
+    pub fn new(
+        mut items: Vec<Statement>,
+        rules: &[rules::Rule],
+        aliases: &[aliases::Alias],
+        strategy: GroupingStrategy,
+    ) -> Self {
         let id = uuid::Uuid::new_v4().to_string();
 
-        let mut grouped: HashMap<String, GroupedStatement> = HashMap::new();
-
-        items.iter().for_each(|x| {
-            let key = format!(
-                "{} {}",
-                x.description
-                    .as_ref()
-                    .map(|x| x.label.as_str())
-                    .unwrap_or(""),
-                x.tegenpartij.as_deref().unwrap_or("")
-            );
-            if let Some(gs) = grouped.get_mut(&key) {
-                gs.add_statement(x)
-            } else {
-                let mut statemens = GroupedStatement::new(key.clone());
-                statemens.add_statement(x);
-                grouped.insert(key, statemens);
-            }
-        });
-
-        grouped.values_mut().for_each(|x| x.sort());
-        let mut grouped: Vec<GroupedStatement> = grouped.into_values().collect();
-
-        grouped.sort_by_key(|x| x.statements.iter().map(|x| x.amount).sum::<isize>());
+        let aliases = aliases::Compiled::compile(aliases);
+        for item in items.iter_mut() {
+            item.payee = aliases.payee(item).map(String::from);
+        }
 
+        let mut grouped = strategy.group(items);
         grouped.push(GroupedStatement::new("deleted".to_string()));
 
-        Self { id, grouped }
+        let mut scan = Self {
+            id,
+            grouped,
+            history: history::History::default(),
+            source: None,
+        };
+        scan.apply_rules(rules);
+        scan
     }
 
     pub fn delete_item(&mut self, group_id: &str, item_id: &str) {
+        if group_id == "deleted" {
+            return;
+        }
+
         let mut item = None;
-        if let Some(group) = self.grouped.iter_mut().filter(|x| x.key == group_id).next() {
-            if let Some(x) = group.statements.iter().filter(|x| x.id.0 == item_id).next() {
+        if let Some(group) = self.grouped.iter_mut().find(|x| x.key == group_id) {
+            if let Some(x) = group.statements.iter().find(|x| x.id.0 == item_id) {
                 item = Some(x.clone());
             }
 
             group.statements.retain(|x| x.id.0 != item_id)
         }
 
-        if let Some(y) = item {
+        if let Some(mut y) = item {
+            y.deleted_from = Some(group_id.to_string());
             self.grouped.last_mut().unwrap().add_statement(&y);
         }
     }
 
+    /// Moves all statements of the group to the "deleted" group, they can still be restored
     pub fn delete(&mut self, id: &str) {
-        self.grouped.retain(|x| x.key != id);
+        if id == "deleted" {
+            return;
+        }
+
+        let index = match self.grouped.iter().position(|x| x.key == id) {
+            Some(x) => x,
+            None => return,
+        };
+        let group = self.grouped.remove(index);
+        for mut statement in group.statements {
+            statement.deleted_from = Some(group.key.clone());
+            self.grouped.last_mut().unwrap().add_statement(&statement);
+        }
+    }
+
+    pub fn get_first(&self) -> Option<&GroupedStatement> {
+        self.grouped
+            .iter()
+            .filter(|x| x.key != "deleted")
+            .find(|x| x.needs_categorised())
     }
 
-    pub fn get_first<'a>(&'a self) -> Option<&'a GroupedStatement> {
-        self.grouped.iter().filter(|x| x.needs_categorised()).next()
+    /// Every statement is categorised and no suspected duplicate is left to review,
+    /// only then the scan can be posted or exported
+    pub fn is_ready(&self) -> bool {
+        self.get_first().is_none() && self.duplicates().next().is_none()
     }
 
     pub fn count_done(&self) -> (usize, usize) {
         let done = self
             .grouped
             .iter()
+            .filter(|x| x.key != "deleted")
             .filter(|x| !x.needs_categorised())
             .map(|x| x.statements.len())
             .sum();
 
-        let total = self.grouped.iter().map(|x| x.statements.len()).sum();
+        let total = self
+            .grouped
+            .iter()
+            .filter(|x| x.key != "deleted")
+            .map(|x| x.statements.len())
+            .sum();
         (done, total)
     }
 
+    /// The transactions of all statements, sorted by date
+    /// Statements of a mapped account of ours are paid from that account, the others from `pay`
+    pub fn outputs<'a>(
+        &'a self,
+        pay: &'a str,
+        own: &'a [own_accounts::OwnAccount],
+        config: &output::OutputConfig,
+    ) -> Vec<ScanOutput<'a, 'a>> {
+        let mut items: Vec<_> = self
+            .grouped
+            .iter()
+            .filter(|x| x.key != "deleted")
+            .flat_map(|x| x.statements.iter())
+            .collect();
+        items.sort_by_key(|x| x.date);
+
+        items
+            .into_iter()
+            .map(|x| x.to_output(x.pay_account(own, pay), config))
+            .collect()
+    }
+
+    /// The transactions followed by the balance assertions, when the config wants them
+    pub fn export(
+        &self,
+        exporter: &dyn exporter::Exporter,
+        pay: &str,
+        own: &[own_accounts::OwnAccount],
+        config: &output::OutputConfig,
+    ) -> String {
+        let mut out = exporter.write(&self.outputs(pay, own, config));
+        if config.balances {
+            out.push_str(&exporter.balances(&self.balances(pay, own)));
+        }
+        out
+    }
+
+    /// The text that is appended to the ledger, the receivable accounts it needs are opened first
+    pub fn to_ledger(
+        &self,
+        pay: &str,
+        own: &[own_accounts::OwnAccount],
+        config: &output::OutputConfig,
+        opened: &[String],
+    ) -> String {
+        let mut out = self.opens(opened);
+        out.push_str(&self.export(&exporter::Beancount, pay, own, config));
+        out
+    }
+
+    pub fn statement_mut(&mut self, group_id: &str, item_id: &str) -> Option<&mut Statement> {
+        self.grouped
+            .iter_mut()
+            .find(|x| x.key == group_id)?
+            .statements
+            .iter_mut()
+            .find(|x| x.id.0 == item_id)
+    }
+
     pub fn categorise(&mut self, uuid: &str, category: &str) {
-        if let Some(item) = self.grouped.iter_mut().filter(|x| x.key == uuid).next() {
+        if let Some(item) = self.grouped.iter_mut().find(|x| x.key == uuid) {
             item.statements
                 .iter_mut()
                 .for_each(|x| x.category = Some(category.to_string()));
@@ -291,19 +420,50 @@ impl Scan {
 
 pub struct ScanOutput<'r, 'a> {
     date: &'a NaiveDate,
-    name: String,
+    payee: Option<String>,
+    narration: String,
+    metadata: Vec<(String, String)>,
+    tags: Vec<String>,
+    links: Vec<String>,
     pay: &'r str,
-    amount: f32,
-    category: String,
+    amount: money::Money,
+    splits: Vec<(&'a str, money::Money)>,
+    /// Takes what is left after the splits, nothing is left when the splits cover everything
+    category: Option<String>,
+    /// Amount of the category posting in a foreign currency and its total cost
+    price: Option<(money::Money, money::Money)>,
 }
 
 use std::fmt;
 impl fmt::Display for ScanOutput<'_, '_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let date_str = self.date.format("%Y-%m-%d");
-        writeln!(f, "{} * \"{}\"", date_str, self.name)?;
-        writeln!(f, "    {} {:.2}", self.pay, self.amount)?;
-        writeln!(f, "    {}", self.category)?;
+        write!(f, "{} *", date_str)?;
+        if let Some(payee) = &self.payee {
+            write!(f, " {}", output::quote(payee))?;
+        }
+        write!(f, " {}", output::quote(&self.narration))?;
+        for tag in self.tags.iter() {
+            write!(f, " #{}", tag)?;
+        }
+        for link in self.links.iter() {
+            write!(f, " ^{}", link)?;
+        }
+        writeln!(f)?;
+        for (key, value) in self.metadata.iter() {
+            writeln!(f, "    {}: {}", key, output::quote(value))?;
+        }
+        writeln!(f, "    {} {}", self.pay, self.amount)?;
+        for (account, amount) in self.splits.iter() {
+            writeln!(f, "    {} {}", account, amount)?;
+        }
+        match (&self.category, &self.price) {
+            (Some(category), Some((original, cost))) => {
+                writeln!(f, "    {} {} @@ {}", category, original, cost)?
+            }
+            (Some(category), None) => writeln!(f, "    {}", category)?,
+            (None, _) => {}
+        }
         Ok(())
     }
 }
@@ -0,0 +1,299 @@
+use std::fmt;
+
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+/// Currency of statements that don't say otherwise
+pub const DEFAULT_CURRENCY: &str = "EUR";
+
+/// Currencies that show up in descriptions of card payments abroad
+pub const CURRENCIES: &[&str] = &[
+    "EUR", "USD", "GBP", "CHF", "JPY", "CAD", "AUD", "SEK", "NOK", "DKK", "PLN", "CZK", "HUF",
+    "RON", "TRY", "CNY", "HKD", "SGD", "NZD", "ZAR", "MAD", "THB", "ISK",
+];
+
+/// An amount in cents with its commodity
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Money {
+    pub cents: isize,
+    pub currency: String,
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", format_cents(self.cents), self.currency)
+    }
+}
+
+/// Cents as a beancount number, `-1234` is `-12.34`
+pub fn format_cents(cents: isize) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    format!("{}{}.{:02}", sign, cents.abs() / 100, cents.abs() % 100)
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum MoneyError {
+    #[error("Empty amount.")]
+    Empty,
+    #[error("Invalid amount '{0}'.")]
+    Invalid(String),
+    #[error("Amount '{0}' has more than two decimals.")]
+    TooPrecise(String),
+    #[error("Amount '{0}' is too large.")]
+    Overflow(String),
+}
+
+/// Currency signs that can stand before or after an amount
+const SYMBOLS: &[char] = &['€', '$', '£', '¥', '₹', '₽', '₩', '₺', '₪'];
+
+/// Strips a currency sign or a three letter code (`EUR`) from either end of `s`
+fn strip_currency(s: &str) -> &str {
+    let s = s.trim().trim_matches(SYMBOLS).trim();
+    let is_code = |x: Option<&str>| {
+        x.map(|x| x.chars().all(|c| c.is_ascii_uppercase()))
+            .unwrap_or(false)
+    };
+
+    let s = match (s.get(..3), s.get(3..)) {
+        (code, Some(rest)) if is_code(code) && !rest.starts_with(char::is_alphabetic) => rest,
+        _ => s,
+    };
+    let s = s.trim();
+    let split = s.len().saturating_sub(3);
+    let s = match (s.get(..split), s.get(split..)) {
+        (Some(rest), code) if is_code(code) && !rest.ends_with(char::is_alphabetic) => rest,
+        _ => s,
+    };
+    s.trim()
+}
+
+/// Removes spaces and apostrophes that separate thousands (`1 234,56`, `1'234.56`),
+/// every part after one has to start with a group of three digits
+fn join_groups(number: &str) -> Option<String> {
+    let mut parts = number.split(|c: char| c.is_whitespace() || c == '\'');
+    let mut out = parts.next()?.to_string();
+    for part in parts {
+        let digits = part.chars().take_while(char::is_ascii_digit).count();
+        let rest = &part[digits..];
+        if digits != 3 || !(rest.is_empty() || rest.starts_with(['.', ','])) {
+            return None;
+        }
+        out.push_str(part);
+    }
+    Some(out)
+}
+
+/// Picks the decimal separator of a number that only contains digits, '.' and ','.
+///
+/// When both separators occur the last one is the decimal separator (`1.234,56` and `1,234.56`).
+/// A single separator is a thousands separator when it occurs more than once or when it is
+/// followed by exactly three digits (`1.234`), otherwise it is the decimal separator.
+fn decimal_separator(number: &str) -> Option<char> {
+    let last_comma = number.rfind(',');
+    let last_dot = number.rfind('.');
+
+    match (last_comma, last_dot) {
+        (Some(c), Some(d)) => Some(if c > d { ',' } else { '.' }),
+        (None, None) => None,
+        (Some(i), None) | (None, Some(i)) => {
+            let sep = if last_comma.is_some() { ',' } else { '.' };
+            let count = number.matches(sep).count();
+            let after = number.len() - i - 1;
+            let before = number[..i].trim_start_matches('0');
+            if count > 1 || (after == 3 && !before.is_empty() && before.len() <= 3) {
+                None
+            } else {
+                Some(sep)
+            }
+        }
+    }
+}
+
+/// Parses a bank amount straight into cents.
+///
+/// Understands European (`-1.234,56`) and US (`-1,234.56`) notation, currency signs and codes,
+/// leading or trailing signs and accounting style negatives (`(12.00)`).
+/// Anything else around or inside the number makes it invalid.
+pub fn parse_cents(input: &str) -> Result<isize, MoneyError> {
+    let invalid = || MoneyError::Invalid(input.to_string());
+
+    let mut number = input.trim();
+    if number.is_empty() {
+        return Err(MoneyError::Empty);
+    }
+
+    let mut negative = false;
+    if let Some(inner) = number.strip_prefix('(').and_then(|x| x.strip_suffix(')')) {
+        negative = true;
+        number = inner;
+    }
+
+    // Signs and currencies go in any order around the number, `-€12.50` or `12,50 EUR-`
+    let mut signed = false;
+    loop {
+        let before = number;
+        number = strip_currency(number);
+        if !signed {
+            if let Some(rest) = number.strip_prefix('-') {
+                negative = !negative;
+                signed = true;
+                number = rest;
+            } else if let Some(rest) = number.strip_prefix('+') {
+                signed = true;
+                number = rest;
+            } else if let Some(rest) = number.strip_suffix('-') {
+                negative = !negative;
+                signed = true;
+                number = rest;
+            }
+        }
+        if number == before {
+            break;
+        }
+    }
+    if number.is_empty() {
+        return Err(MoneyError::Empty);
+    }
+
+    let joined = join_groups(number).ok_or_else(invalid)?;
+    let number = joined.as_str();
+
+    if number.is_empty()
+        || !number
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '.' || c == ',')
+    {
+        return Err(invalid());
+    }
+
+    let (whole, fraction) = match decimal_separator(number) {
+        Some(sep) => number.rsplit_once(sep).ok_or_else(invalid)?,
+        None => (number, ""),
+    };
+
+    // Thousands separators have to group by three
+    let mut groups = whole.split(['.', ',']);
+    let first = groups.next().unwrap_or_default();
+    if groups.any(|x| x.len() != 3) || (first.is_empty() && whole.len() > 1) {
+        return Err(invalid());
+    }
+
+    let whole: String = whole.chars().filter(char::is_ascii_digit).collect();
+    if fraction.contains(|c: char| !c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(invalid());
+    }
+
+    // Trailing zeros are harmless, anything else would need rounding
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > 2 {
+        return Err(MoneyError::TooPrecise(input.to_string()));
+    }
+
+    let overflow = || MoneyError::Overflow(input.to_string());
+    let whole: isize = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().map_err(|_| overflow())?
+    };
+    let fraction: isize = format!("{:0<2}", fraction).parse().map_err(|_| invalid())?;
+
+    let cents = whole
+        .checked_mul(100)
+        .and_then(|x| x.checked_add(fraction))
+        .ok_or_else(overflow)?;
+
+    Ok(if negative { -cents } else { cents })
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<isize, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    parse_cents(&s).map_err(de::Error::custom)
+}
+
+/// A balance column, a balance that can't be read is left out instead of failing the row
+pub fn deserialize_balance<'de, D>(deserializer: D) -> Result<Option<isize>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    Ok(parse_cents(&s).ok())
+}
+
+pub fn default_currency() -> String {
+    DEFAULT_CURRENCY.to_string()
+}
+
+/// A currency column, empty cells are the default currency
+pub fn deserialize_currency<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    let s = s.trim().to_uppercase();
+    Ok(if s.is_empty() { default_currency() } else { s })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_amounts() {
+        let cases = [
+            ("1.234,56", 123456),
+            ("1,234.56", 123456),
+            ("1.234", 123400),
+            ("12,5", 1250),
+            ("(12,50)", -1250),
+            ("-€12.50", -1250),
+            ("€ -12.50", -1250),
+            ("12,50 EUR", 1250),
+            ("EUR -12,50", -1250),
+            ("12,50-", -1250),
+            ("+3", 300),
+            ("1 234,56", 123456),
+            ("1'234.56", 123456),
+            ("0,10", 10),
+        ];
+        for (input, cents) in cases.iter() {
+            assert_eq!(parse_cents(input), Ok(*cents), "{}", input);
+        }
+
+        assert_eq!(parse_cents(""), Err(MoneyError::Empty));
+        assert_eq!(parse_cents(" EUR "), Err(MoneyError::Empty));
+        // A lone separator before three digits groups thousands
+        assert_eq!(parse_cents("1,234"), Ok(123400));
+        assert_eq!(
+            parse_cents("1.5.6"),
+            Err(MoneyError::Invalid("1.5.6".to_string()))
+        );
+        assert_eq!(
+            parse_cents("0,125"),
+            Err(MoneyError::TooPrecise("0,125".to_string()))
+        );
+
+        for invalid in [
+            "1e5",
+            "12,50 EUR 3",
+            "12 50",
+            "abc",
+            "--12",
+            "12x",
+            "1.23.4,5",
+        ]
+        .iter()
+        {
+            assert_eq!(
+                parse_cents(invalid),
+                Err(MoneyError::Invalid(invalid.to_string())),
+                "{}",
+                invalid
+            );
+        }
+    }
+}
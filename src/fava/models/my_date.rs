@@ -23,6 +23,18 @@ where
     NaiveDate::parse_from_str(&s, FORMAT).map_err(serde::de::Error::custom)
 }
 
+/// Banks don't agree on a date format, try the common ones
+pub fn deserialize_lenient<'de, D>(deserializer: D) -> Result<NaiveDate, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    ["%d/%m/%Y", "%d-%m-%Y", "%Y-%m-%d", "%d.%m.%Y"]
+        .iter()
+        .find_map(|f| NaiveDate::parse_from_str(s.trim(), f).ok())
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid date '{}'", s)))
+}
+
 pub fn deserialize_spacy_string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
 where
     D: de::Deserializer<'de>,
@@ -0,0 +1,79 @@
+use super::Statement;
+
+/// The twelve digits of a structured communication, `+++090/9337/55493+++` is `090933755493`.
+/// Banks also write it with `***` or without any decoration.
+pub fn parse(text: &str) -> Option<String> {
+    let text = text.trim();
+    let inner = ["+++", "***"]
+        .iter()
+        .find_map(|x| text.strip_prefix(x)?.strip_suffix(x))
+        .unwrap_or(text);
+    if !inner
+        .chars()
+        .all(|c| c.is_ascii_digit() || c == '/' || c.is_whitespace())
+    {
+        return None;
+    }
+
+    let digits: String = inner.chars().filter(char::is_ascii_digit).collect();
+    Some(digits).filter(|x| x.len() == 12)
+}
+
+/// The last two digits are the first ten modulo 97, or 97 when that is 0
+pub fn is_valid(digits: &str) -> bool {
+    let (base, check) = match (digits.get(..10), digits.get(10..)) {
+        (Some(base), Some(check)) if digits.len() == 12 => (base, check),
+        _ => return false,
+    };
+    match (base.parse::<u64>(), check.parse::<u64>()) {
+        (Ok(base), Ok(check)) => check == if base % 97 == 0 { 97 } else { base % 97 },
+        _ => false,
+    }
+}
+
+/// `+++090/9337/55493+++`, the way it is printed on an invoice
+pub fn format(digits: &str) -> String {
+    match (digits.get(..3), digits.get(3..7), digits.get(7..)) {
+        (Some(a), Some(b), Some(c)) => format!("+++{}/{}/{}+++", a, b, c),
+        _ => digits.to_string(),
+    }
+}
+
+impl Statement {
+    /// The structured communication as the bank wrote it
+    pub fn structured(&self) -> Option<&str> {
+        self.note.structured.as_deref()
+    }
+
+    /// The digits of the structured communication, only when its check digits are right
+    pub fn ogm(&self) -> Option<String> {
+        self.structured().and_then(parse).filter(|x| is_valid(x))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_digits() {
+        assert_eq!(
+            parse("+++090/9337/55493+++").as_deref(),
+            Some("090933755493")
+        );
+        assert_eq!(
+            parse("***090/9337/55493***").as_deref(),
+            Some("090933755493")
+        );
+        assert_eq!(parse(" 090933755493 ").as_deref(), Some("090933755493"));
+        assert_eq!(parse("+++090/9337/5549+++"), None);
+        assert_eq!(parse("invoice 090933755493"), None);
+
+        assert!(is_valid("090933755493"));
+        assert!(is_valid("000000009797"));
+        assert!(!is_valid("090933755494"));
+        assert!(!is_valid("0909337554"));
+
+        assert_eq!(format("090933755493"), "+++090/9337/55493+++");
+    }
+}
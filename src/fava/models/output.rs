@@ -0,0 +1,134 @@
+use regex::{Captures, Regex};
+use rocket::serde::Deserialize;
+
+use super::{iban, ogm, Statement};
+
+lazy_static! {
+    static ref PLACEHOLDER: Regex = Regex::new(r"\{([a-z|-]+)\}").unwrap();
+}
+
+/// How statements are written to the ledger.
+///
+/// Payee, narration, metadata values, tags and links are templates: `{name}` is replaced by a
+/// field of the statement and `{a|b}` takes the first of the two that is not empty.
+/// Known fields are `import-id`, `bank-ref`, `end-to-end`, `value-date`, `iban`, `bic`, `note`,
+/// `structured`, `free`, `ogm`, `invoice`, `payee`, `counterparty`, `label`, `way`, `user`,
+/// `kind` and `description`. `payee` is the canonical payee given by an alias, `ogm` is the
+/// structured communication when its check digits are right.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct OutputConfig {
+    pub payee: String,
+    pub narration: String,
+    /// Metadata keys, each one is filled with the field of the same name
+    pub metadata: Vec<String>,
+    pub tags: Vec<String>,
+    pub links: Vec<String>,
+    /// Assert the balance the bank gives at the end of every imported day
+    pub balances: bool,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            payee: "{payee|counterparty|label}".to_string(),
+            narration: "{note|label}".to_string(),
+            metadata: ["import-id", "bank-ref", "note", "iban", "ogm", "invoice"]
+                .iter()
+                .map(|x| x.to_string())
+                .collect(),
+            tags: Vec::new(),
+            links: Vec::new(),
+            balances: true,
+        }
+    }
+}
+
+/// Tags and links only allow a few characters
+fn sanitise(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "-_/.".contains(c) {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+impl Statement {
+    fn field(&self, name: &str) -> Option<String> {
+        let description = self.description.as_ref();
+        let value = match name {
+            "import-id" | "id" => Some(self.id.0.clone()),
+            "bank-ref" => self.reference.clone(),
+            "end-to-end" => self.end_to_end.clone(),
+            "value-date" => self.value_date.map(|x| x.format("%Y-%m-%d").to_string()),
+            "iban" => self
+                .iban()
+                .or_else(|| self.counter_account.as_deref().map(iban::normalise)),
+            "bic" => self.bic(),
+            "note" => match (&self.note.structured, &self.note.free) {
+                (Some(a), Some(b)) => Some(format!("{} {}", a, b)),
+                (a, b) => a.clone().or_else(|| b.clone()),
+            },
+            "structured" => self.note.structured.clone(),
+            "free" => self.note.free.clone(),
+            "ogm" => self.ogm().as_deref().map(ogm::format),
+            "invoice" => self.invoice.clone(),
+            "payee" => self.payee.clone(),
+            "counterparty" => self.tegenpartij.clone(),
+            "label" => description.map(|x| x.label.clone()),
+            "way" => description.map(|x| x.way.clone()),
+            "user" => description.and_then(|x| x.user.clone()),
+            "kind" => description.map(|x| x.kind.name().to_string()),
+            "description" => description.map(|x| x.text.clone()),
+            _ => None,
+        };
+        value.filter(|x| !x.trim().is_empty())
+    }
+
+    pub fn render(&self, template: &str) -> String {
+        PLACEHOLDER
+            .replace_all(template, |caps: &Captures| {
+                caps[1]
+                    .split('|')
+                    .find_map(|x| self.field(x))
+                    .unwrap_or_default()
+            })
+            .trim()
+            .to_string()
+    }
+
+    pub fn metadata(&self, config: &OutputConfig) -> Vec<(String, String)> {
+        config
+            .metadata
+            .iter()
+            .filter_map(|key| Some((key.clone(), self.field(key)?)))
+            .collect()
+    }
+
+    pub fn tags(&self, config: &OutputConfig) -> Vec<String> {
+        config
+            .tags
+            .iter()
+            .map(|x| sanitise(&self.render(x)))
+            .filter(|x| !x.is_empty())
+            .collect()
+    }
+
+    pub fn links(&self, config: &OutputConfig) -> Vec<String> {
+        config
+            .links
+            .iter()
+            .map(|x| sanitise(&self.render(x)))
+            .filter(|x| !x.is_empty())
+            .collect()
+    }
+}
+
+/// A beancount string
+pub fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
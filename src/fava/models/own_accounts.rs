@@ -0,0 +1,79 @@
+use rocket::serde::{Deserialize, Serialize};
+
+use super::{iban, Scan, Statement};
+use crate::repository::Repository;
+
+/// One of our own bank accounts and the asset account its statements are booked on
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OwnAccount {
+    pub id: String,
+    /// Normalised, without spaces
+    pub iban: String,
+    pub account: String,
+}
+
+pub type OwnAccounts = Repository<Vec<OwnAccount>>;
+
+impl OwnAccount {
+    pub fn new(account_number: &str, account: &str) -> Result<Self, String> {
+        let number = iban::normalise(account_number);
+        if number.is_empty() {
+            return Err("A mapping needs an account number.".to_string());
+        }
+        if !account.starts_with("Assets:") {
+            return Err(format!("'{}' is not an Assets account.", account));
+        }
+
+        Ok(Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            iban: number,
+            account: account.to_string(),
+        })
+    }
+}
+
+/// The asset account of a bank account, when it is mapped
+pub fn lookup<'a>(own: &'a [OwnAccount], account_number: &str) -> Option<&'a str> {
+    let number = iban::normalise(account_number);
+    own.iter()
+        .find(|x| x.iban == number)
+        .map(|x| x.account.as_str())
+}
+
+impl Statement {
+    /// Our own account this statement was booked on, normalised
+    pub fn own_iban(&self) -> Option<String> {
+        self.own_account
+            .as_deref()
+            .map(iban::normalise)
+            .filter(|x| !x.is_empty())
+    }
+
+    /// The account that pays, `fallback` when our account is unknown or not mapped
+    pub fn pay_account<'a>(&self, own: &'a [OwnAccount], fallback: &'a str) -> &'a str {
+        self.own_account
+            .as_deref()
+            .and_then(|x| lookup(own, x))
+            .unwrap_or(fallback)
+    }
+}
+
+impl Scan {
+    /// Our own accounts in this scan with their number of statements, in order of appearance
+    pub fn own_accounts(&self) -> Vec<(String, usize)> {
+        let mut found: Vec<(String, usize)> = Vec::new();
+        let statements = self
+            .grouped
+            .iter()
+            .filter(|x| x.key != "deleted")
+            .flat_map(|x| x.statements.iter());
+
+        for number in statements.filter_map(Statement::own_iban) {
+            match found.iter_mut().find(|(x, _)| *x == number) {
+                Some((_, count)) => *count += 1,
+                None => found.push((number, 1)),
+            }
+        }
+        found
+    }
+}
@@ -0,0 +1,158 @@
+use regex::Regex;
+use rocket::serde::{Deserialize, Serialize};
+
+use super::{iban, GroupedStatement, Scan, Statement};
+use crate::repository::Repository;
+
+/// Text of a statement a rule can look at
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Field {
+    Label,
+    Tegenpartij,
+    Way,
+    Note,
+    /// Only valid IBANs, without spaces
+    Iban,
+}
+
+impl Field {
+    pub const ALL: &'static [(&'static str, Field)] = &[
+        ("label", Field::Label),
+        ("tegenpartij", Field::Tegenpartij),
+        ("way", Field::Way),
+        ("note", Field::Note),
+        ("iban", Field::Iban),
+    ];
+
+    fn get(self, statement: &Statement) -> Option<String> {
+        match self {
+            Field::Label => statement.description.as_ref().map(|x| x.label.clone()),
+            Field::Tegenpartij => statement.tegenpartij.clone(),
+            Field::Way => statement.description.as_ref().map(|x| x.way.clone()),
+            Field::Note => {
+                let note = &statement.note;
+                match (&note.structured, &note.free) {
+                    (Some(a), Some(b)) => Some(format!("{} {}", a, b)),
+                    (a, b) => a.clone().or_else(|| b.clone()),
+                }
+            }
+            Field::Iban => statement.iban(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Condition {
+    /// Case insensitive, ignoring surrounding whitespace
+    Exact {
+        field: Field,
+        value: String,
+    },
+    Regex {
+        field: Field,
+        pattern: String,
+    },
+    /// Inclusive bounds in cents
+    Amount {
+        min: Option<isize>,
+        max: Option<isize>,
+    },
+}
+
+/// Assigns `account` to every statement that satisfies `condition`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Rule {
+    pub id: String,
+    pub condition: Condition,
+    pub account: String,
+}
+
+pub type Rules = Repository<Vec<Rule>>;
+
+impl Rule {
+    pub fn new(condition: Condition, account: String) -> Result<Self, regex::Error> {
+        if let Condition::Regex { pattern, .. } = &condition {
+            Regex::new(pattern)?;
+        }
+
+        Ok(Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            condition,
+            account,
+        })
+    }
+}
+
+/// A rule with its regex compiled, rules are checked against every statement of a scan
+struct Compiled<'a> {
+    rule: &'a Rule,
+    regex: Option<Regex>,
+}
+
+impl<'a> Compiled<'a> {
+    fn compile(rules: &'a [Rule]) -> Vec<Self> {
+        rules
+            .iter()
+            .map(|rule| {
+                let regex = match &rule.condition {
+                    Condition::Regex { pattern, .. } => Regex::new(pattern).ok(),
+                    _ => None,
+                };
+                Compiled { rule, regex }
+            })
+            .collect()
+    }
+
+    fn matches(&self, statement: &Statement) -> bool {
+        match &self.rule.condition {
+            Condition::Exact {
+                field: Field::Iban,
+                value,
+            } => Field::Iban.get(statement) == Some(iban::normalise(value)),
+            Condition::Exact { field, value } => field
+                .get(statement)
+                .map(|x| x.trim().eq_ignore_ascii_case(value.trim()))
+                .unwrap_or(false),
+            Condition::Regex { field, .. } => match (&self.regex, field.get(statement)) {
+                (Some(regex), Some(x)) => regex.is_match(&x),
+                _ => false,
+            },
+            Condition::Amount { min, max } => {
+                min.map(|min| statement.amount >= min).unwrap_or(true)
+                    && max.map(|max| statement.amount <= max).unwrap_or(true)
+            }
+        }
+    }
+
+    fn matches_group(&self, group: &GroupedStatement) -> bool {
+        !group.statements.is_empty() && group.statements.iter().all(|x| self.matches(x))
+    }
+}
+
+impl Scan {
+    /// Categorises every uncategorised group of which all statements match the same rule.
+    /// Rules are tried in order, the first one wins.
+    pub fn apply_rules(&mut self, rules: &[Rule]) -> usize {
+        let compiled = Compiled::compile(rules);
+
+        let found: Vec<_> = self
+            .grouped
+            .iter()
+            .filter(|x| x.needs_categorised() && x.key != "deleted")
+            .filter_map(|group| {
+                compiled
+                    .iter()
+                    .find(|x| x.matches_group(group))
+                    .map(|x| (group.key.clone(), x.rule.account.clone()))
+            })
+            .collect();
+
+        for (key, account) in found.iter() {
+            self.categorise(key, account);
+        }
+
+        found.len()
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+
+use super::ledger::Transaction;
+use super::split::{Share, Split};
+use super::{Scan, Statement};
+
+/// The shares of other people are booked here, per person, until they pay them back
+pub const RECEIVABLE: &str = "Assets:Receivable";
+
+/// Someone a statement is shared with and how many parts they take
+#[derive(Debug, Clone, PartialEq)]
+pub struct Person {
+    pub name: String,
+    pub weight: u32,
+}
+
+impl Person {
+    /// `me` is us, our share stays in the category of the statement
+    fn is_me(&self) -> bool {
+        self.name.eq_ignore_ascii_case("me")
+    }
+}
+
+/// The account a person's share goes to, `anna` owes on `Assets:Receivable:Anna`
+pub fn receivable(name: &str) -> String {
+    format!("{}:{}", RECEIVABLE, name)
+}
+
+/// The person of a receivable account
+pub fn person(account: &str) -> Option<&str> {
+    account
+        .strip_prefix(RECEIVABLE)?
+        .strip_prefix(':')
+        .filter(|x| !x.is_empty())
+}
+
+/// A name that can be part of an account: capitalised, spaces become dashes
+fn account_name(name: &str) -> Result<String, String> {
+    let name = name.trim().replace(char::is_whitespace, "-");
+    let mut chars = name.chars();
+    let name: String = match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => return Err("A person needs a name.".to_string()),
+    };
+
+    let valid = name.starts_with(|c: char| c.is_ascii_uppercase())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    if valid {
+        Ok(name)
+    } else {
+        Err(format!("'{}' can't be used in an account name.", name))
+    }
+}
+
+/// `Anna, Bert:2, me`: everyone takes one part unless a weight follows the name.
+/// We take one part as well, unless `me` is given with another weight.
+pub fn parse(text: &str) -> Result<Vec<Person>, String> {
+    let mut people = Vec::new();
+    for part in text.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+        let (name, weight) = match part.rsplit_once(':') {
+            Some((name, weight)) => {
+                let weight = weight
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("Invalid weight '{}'.", weight.trim()))?;
+                (name, weight)
+            }
+            None => (part, 1),
+        };
+
+        let person = Person {
+            name: account_name(name)?,
+            weight,
+        };
+        if people.iter().any(|x: &Person| x.name == person.name) {
+            return Err(format!("{} is given twice.", person.name));
+        }
+        people.push(person);
+    }
+
+    if !people.iter().any(|x| !x.is_me()) {
+        return Err("Share with at least one other person.".to_string());
+    }
+    if !people.iter().any(Person::is_me) {
+        people.push(Person {
+            name: "Me".to_string(),
+            weight: 1,
+        });
+    }
+    Ok(people)
+}
+
+impl Statement {
+    /// Shares what is left after the other splits among `people`, earlier shares are replaced.
+    /// The shares of others become splits to their receivable account, the cents that don't
+    /// divide evenly stay with us.
+    pub fn share(&mut self, people: &[Person]) {
+        self.splits.retain(|x| person(&x.account).is_none());
+
+        let weights: u32 = people.iter().map(|x| x.weight).sum();
+        if weights == 0 {
+            return;
+        }
+        let left = self.remainder().abs();
+        for other in people.iter().filter(|x| !x.is_me()) {
+            let cents = left * other.weight as isize / weights as isize;
+            if cents > 0 {
+                self.splits.push(Split {
+                    account: receivable(&other.name),
+                    share: Share::Amount(cents),
+                });
+            }
+        }
+    }
+}
+
+impl Scan {
+    /// Opens the receivable accounts this scan needs that `opened` doesn't have yet
+    pub fn opens(&self, opened: &[String]) -> String {
+        self.receivables()
+            .into_iter()
+            .filter(|(account, _)| !opened.contains(account))
+            .map(|(account, date)| format!("\n{} open {}\n", date.format("%Y-%m-%d"), account))
+            .collect()
+    }
+
+    /// Shares every statement of the group the same way
+    pub fn share_group(&mut self, group_id: &str, people: &[Person]) {
+        if let Some(group) = self.grouped.iter_mut().find(|x| x.key == group_id) {
+            for statement in group.statements.iter_mut() {
+                statement.share(people);
+            }
+        }
+    }
+
+    /// Receivable accounts this scan books on, with the date they are first needed
+    pub fn receivables(&self) -> Vec<(String, chrono::NaiveDate)> {
+        let mut found: BTreeMap<String, chrono::NaiveDate> = BTreeMap::new();
+        let statements = self
+            .grouped
+            .iter()
+            .filter(|x| x.key != "deleted")
+            .flat_map(|x| x.statements.iter());
+        for statement in statements {
+            let accounts = statement.splits.iter().map(|x| x.account.as_str());
+            for account in accounts.chain(statement.category.as_deref()) {
+                if person(account).is_some() {
+                    let date = found.entry(account.to_string()).or_insert(statement.date);
+                    *date = (*date).min(statement.date);
+                }
+            }
+        }
+        found.into_iter().collect()
+    }
+}
+
+/// What everyone owes us according to the ledger, per currency.
+/// A negative amount is what we owe them.
+pub fn balances(ledger: &[Transaction]) -> BTreeMap<String, BTreeMap<String, isize>> {
+    let mut out: BTreeMap<String, BTreeMap<String, isize>> = BTreeMap::new();
+    for tx in ledger {
+        let known: Vec<_> = tx
+            .postings
+            .iter()
+            .filter_map(|x| Some((x.amount?, x.currency.as_deref()?)))
+            .collect();
+        // Paying back is usually booked with the amount left out, it balances the others
+        let elided = match known.first() {
+            Some((_, currency)) if known.iter().all(|(_, x)| x == currency) => {
+                Some((-known.iter().map(|(x, _)| x).sum::<isize>(), *currency))
+            }
+            _ => None,
+        };
+
+        for posting in tx.postings.iter() {
+            let name = match person(&posting.account) {
+                Some(x) => x,
+                None => continue,
+            };
+            let amount = match (posting.amount, posting.currency.as_deref()) {
+                (Some(amount), Some(currency)) => (amount, currency),
+                _ => match elided {
+                    Some(x) => x,
+                    None => continue,
+                },
+            };
+            *out.entry(name.to_string())
+                .or_default()
+                .entry(amount.1.to_string())
+                .or_default() += amount.0;
+        }
+    }
+    out
+}
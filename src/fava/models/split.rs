@@ -0,0 +1,110 @@
+use std::str::FromStr;
+
+use rocket::serde::{Deserialize, Serialize};
+
+use super::{money, Statement};
+
+/// How much of a statement goes to another account
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(tag = "kind", content = "value", rename_all = "lowercase")]
+pub enum Share {
+    /// Cents, always positive, the sign follows the statement
+    Amount(isize),
+    Percent(f64),
+}
+
+impl FromStr for Share {
+    type Err = String;
+
+    /// `12,50` is an amount, `25%` a percentage
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(percent) = s.strip_suffix('%') {
+            let percent: f64 = percent
+                .trim()
+                .replace(',', ".")
+                .parse()
+                .map_err(|_| format!("Invalid percentage '{}'.", s))?;
+            // NaN compares false with everything, it would be saved as `null`
+            if !percent.is_finite() || percent <= 0.0 || percent > 100.0 {
+                return Err(format!("Percentage '{}' is not between 0 and 100.", s));
+            }
+            Ok(Share::Percent(percent))
+        } else {
+            match money::parse_cents(s).map_err(|e| e.to_string())? {
+                0 => Err("A split needs an amount.".to_string()),
+                cents => Ok(Share::Amount(cents.abs())),
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Split {
+    pub account: String,
+    pub share: Share,
+}
+
+impl Statement {
+    fn share_amount(&self, share: Share) -> isize {
+        match share {
+            Share::Amount(cents) => cents * self.amount.signum(),
+            Share::Percent(percent) => (self.amount as f64 * percent / 100.0).round() as isize,
+        }
+    }
+
+    /// The amount every split takes, with the sign of the statement
+    pub fn split_amounts(&self) -> impl Iterator<Item = (&str, isize)> {
+        self.splits
+            .iter()
+            .map(move |x| (x.account.as_str(), self.share_amount(x.share)))
+    }
+
+    /// What is left for `category` after the splits
+    pub fn remainder(&self) -> isize {
+        self.amount - self.split_amounts().map(|(_, x)| x).sum::<isize>()
+    }
+
+    pub fn add_split(&mut self, account: &str, share: Share) -> Result<(), String> {
+        if account.trim().is_empty() {
+            return Err("A split needs an account.".to_string());
+        }
+
+        let amount = self.share_amount(share);
+        if amount.abs() > self.remainder().abs() {
+            return Err(format!(
+                "Only {:.2} is left to split.",
+                self.remainder().abs() as f64 / 100.0
+            ));
+        }
+
+        self.splits.push(Split {
+            account: account.trim().to_string(),
+            share,
+        });
+        Ok(())
+    }
+
+    pub fn remove_split(&mut self, index: usize) {
+        if index < self.splits.len() {
+            self.splits.remove(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_share() {
+        assert_eq!("25%".parse(), Ok(Share::Percent(25.0)));
+        assert_eq!(" 12,5 % ".parse(), Ok(Share::Percent(12.5)));
+        assert_eq!("12,50".parse(), Ok(Share::Amount(1250)));
+        assert_eq!("-12,50".parse(), Ok(Share::Amount(1250)));
+
+        for invalid in ["nan%", "NaN%", "inf%", "-inf%", "0%", "101%", "abc%", "0"] {
+            assert!(invalid.parse::<Share>().is_err(), "{} is a share", invalid);
+        }
+    }
+}
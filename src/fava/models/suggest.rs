@@ -0,0 +1,139 @@
+use std::collections::{HashMap, HashSet};
+
+use rocket::serde::Serialize;
+
+use super::ledger::Transaction;
+use super::{GroupedStatement, Statement};
+
+/// How many suggestions are shown for a group
+const MAX_SUGGESTIONS: usize = 5;
+/// Suggestions that are less likely than this are noise
+const MIN_CONFIDENCE: f64 = 0.05;
+
+/// Lowercase words of at least two characters, numbers carry no meaning here
+fn tokens(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|x| x.chars().count() > 1 && !x.chars().all(|c| c.is_ascii_digit()))
+        .map(str::to_lowercase)
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Suggestion {
+    pub account: String,
+    /// Between 0 and 1
+    pub confidence: f64,
+}
+
+#[derive(Debug, Default)]
+struct AccountStats {
+    transactions: usize,
+    tokens: usize,
+    counts: HashMap<String, usize>,
+}
+
+/// Multinomial naive Bayes over the payee and narration tokens of the ledger
+#[derive(Debug, Default)]
+pub struct Classifier {
+    accounts: HashMap<String, AccountStats>,
+    vocabulary: HashSet<String>,
+    transactions: usize,
+}
+
+impl Classifier {
+    /// Learns which accounts go with which payees,
+    /// only postings to accounts accepted by `is_category` are learned from.
+    pub fn train(transactions: &[Transaction], is_category: impl Fn(&str) -> bool) -> Self {
+        let mut out = Self::default();
+
+        for tx in transactions {
+            let text = format!("{} {}", tx.payee.as_deref().unwrap_or(""), tx.narration);
+            let words: Vec<_> = tokens(&text).collect();
+            if words.is_empty() {
+                continue;
+            }
+
+            let accounts: HashSet<_> = tx
+                .postings
+                .iter()
+                .map(|x| x.account.as_str())
+                .filter(|x| is_category(x))
+                .collect();
+
+            for account in accounts {
+                let stats = out.accounts.entry(account.to_string()).or_default();
+                stats.transactions += 1;
+                stats.tokens += words.len();
+                for word in words.iter() {
+                    *stats.counts.entry(word.clone()).or_default() += 1;
+                }
+                out.transactions += 1;
+            }
+            out.vocabulary.extend(words);
+        }
+
+        out
+    }
+
+    /// The most likely accounts for `text`, best first
+    pub fn suggest<'a>(&self, text: impl IntoIterator<Item = &'a str>) -> Vec<Suggestion> {
+        let words: HashSet<_> = text
+            .into_iter()
+            .flat_map(tokens)
+            .filter(|x| self.vocabulary.contains(x))
+            .collect();
+        if words.is_empty() {
+            return Vec::new();
+        }
+
+        let vocabulary = self.vocabulary.len() as f64;
+        let mut scores: Vec<_> = self
+            .accounts
+            .iter()
+            .map(|(account, stats)| {
+                let prior = (stats.transactions as f64 / self.transactions as f64).ln();
+                let likelihood: f64 = words
+                    .iter()
+                    .map(|word| {
+                        let count = stats.counts.get(word).copied().unwrap_or(0) as f64;
+                        ((count + 1.0) / (stats.tokens as f64 + vocabulary)).ln()
+                    })
+                    .sum();
+                (account, prior + likelihood)
+            })
+            .collect();
+
+        // Log scores to probabilities, shifted by the max to stay clear of underflow
+        let max = scores
+            .iter()
+            .map(|(_, x)| *x)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let total: f64 = scores.iter().map(|(_, x)| (x - max).exp()).sum();
+
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scores
+            .into_iter()
+            .map(|(account, score)| Suggestion {
+                account: account.clone(),
+                confidence: (score - max).exp() / total,
+            })
+            .filter(|x| x.confidence >= MIN_CONFIDENCE)
+            .take(MAX_SUGGESTIONS)
+            .collect()
+    }
+}
+
+impl Statement {
+    fn words(&self) -> impl Iterator<Item = &str> {
+        self.tegenpartij
+            .as_deref()
+            .into_iter()
+            .chain(self.description.as_ref().map(|x| x.label.as_str()))
+            .chain(self.note.free.as_deref())
+    }
+}
+
+impl GroupedStatement {
+    pub fn suggest(&self, classifier: &Classifier) -> Vec<Suggestion> {
+        classifier.suggest(self.statements.iter().flat_map(Statement::words))
+    }
+}
@@ -0,0 +1,193 @@
+use super::own_accounts::{self, OwnAccount};
+use super::{iban, Scan, Statement};
+
+/// Both banks book a transfer, but not always on the same day
+const MAX_DAYS: i64 = 3;
+
+impl Statement {
+    /// The asset account of our own account this statement was booked on, when it is mapped
+    fn own_asset<'a>(&self, own: &'a [OwnAccount]) -> Option<&'a str> {
+        own_accounts::lookup(own, self.own_account.as_deref()?)
+    }
+
+    /// Opposite amounts within a few days, between two different accounts of ours.
+    /// A bank may leave out the counterparty, but the ones that are given point at each other.
+    pub fn is_transfer_with(&self, other: &Statement) -> bool {
+        let (ours, theirs) = match (self.own_iban(), other.own_iban()) {
+            (Some(ours), Some(theirs)) if ours != theirs => (ours, theirs),
+            _ => return false,
+        };
+        let points_at = |x: &Statement, to: &str| {
+            x.counter_account
+                .as_deref()
+                .map(iban::normalise)
+                .filter(|x| !x.is_empty())
+                .map(|x| x == to)
+        };
+        let named = [points_at(self, &theirs), points_at(other, &ours)];
+
+        self.amount != 0
+            && self.amount == -other.amount
+            && self.currency == other.currency
+            && (self.date - other.date).num_days().abs() <= MAX_DAYS
+            && named.contains(&Some(true))
+            && !named.contains(&Some(false))
+    }
+}
+
+impl Scan {
+    /// Deletes the half of a transfer that is booked by the other half
+    fn drop_transfer(&mut self, group_id: &str, item_id: &str, reason: String) {
+        if let Some(statement) = self.statement_mut(group_id, item_id) {
+            statement.transfer = Some(reason);
+        }
+        self.delete_item(group_id, item_id);
+        self.drop_empty(group_id);
+    }
+
+    /// Books the half of a transfer that stays as a transfer to our other asset account `to`
+    fn book_transfer(&mut self, group_id: &str, item_id: &str, to: &str, reason: String) {
+        let transfer = format!("Transfer to {}", to);
+        self.book(group_id, item_id, &transfer, to);
+        if let Some(statement) = self.statement_mut(&transfer, item_id) {
+            statement.transfer = Some(reason);
+        }
+    }
+
+    /// A group without statements has nothing left to categorise
+    fn drop_empty(&mut self, group_id: &str) {
+        if group_id != "deleted" {
+            self.grouped
+                .retain(|x| x.key != group_id || !x.statements.is_empty());
+        }
+    }
+}
+
+/// Where a statement lives: the index of its scan, its group and the statement itself
+type Place = (usize, String, Statement);
+
+/// Merges the two halves of every transfer between our mapped accounts in the pending scans.
+/// The money going out becomes a transfer to the other asset account, the money coming in is
+/// deleted so the ledger does not count it twice. Both halves are marked, so statements that
+/// were merged before are left alone. Returns the number of merged transfers.
+pub fn merge_transfers(scans: &mut [Scan], own: &[OwnAccount]) -> usize {
+    let places: Vec<Place> = scans
+        .iter()
+        .enumerate()
+        .flat_map(|(i, scan)| {
+            scan.grouped
+                .iter()
+                .filter(|x| x.key != "deleted")
+                .flat_map(move |g| {
+                    g.statements
+                        .iter()
+                        .map(move |x| (i, g.key.clone(), x.clone()))
+                })
+        })
+        .filter(|(_, _, x)| {
+            x.duplicate.is_none() && x.transfer.is_none() && x.own_asset(own).is_some()
+        })
+        .collect();
+
+    let mut taken = vec![false; places.len()];
+    let mut pairs = Vec::new();
+    for (out, (_, _, outgoing)) in places.iter().enumerate() {
+        if outgoing.amount >= 0 {
+            continue;
+        }
+        let incoming = places
+            .iter()
+            .enumerate()
+            .position(|(i, (_, _, x))| !taken[i] && x.amount > 0 && outgoing.is_transfer_with(x));
+        if let Some(incoming) = incoming {
+            taken[incoming] = true;
+            pairs.push((out, incoming));
+        }
+    }
+
+    for &(out, incoming) in pairs.iter() {
+        let (out_scan, out_group, outgoing) = &places[out];
+        let (in_scan, in_group, received) = &places[incoming];
+        let from = outgoing.own_asset(own).unwrap_or_default();
+        let to = received.own_asset(own).unwrap_or_default();
+
+        let reason = format!(
+            "Booked by the transfer from {} in scan {}",
+            from, scans[*out_scan].id
+        );
+        scans[*in_scan].edit("Merge a transfer", |scan| {
+            scan.drop_transfer(in_group, &received.id.0, reason)
+        });
+        let reason = format!("Received on {} in scan {}", to, scans[*in_scan].id);
+        scans[*out_scan].edit("Merge a transfer", |scan| {
+            scan.book_transfer(out_group, &outgoing.id.0, to, reason)
+        });
+    }
+
+    pairs.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::super::GroupingStrategy;
+    use super::*;
+
+    const CURRENT: &str = "BE68539007547034";
+    const SAVINGS: &str = "BE71096123456769";
+
+    fn statement(day: u32, cents: isize, ours: &str, theirs: &str) -> Statement {
+        let mut statement = Statement::new(NaiveDate::from_ymd_opt(2023, 1, day).unwrap(), cents);
+        statement.own_account = Some(ours.to_string());
+        statement.counter_account = Some(theirs.to_string());
+        statement
+    }
+
+    fn scan(statements: Vec<Statement>) -> Scan {
+        Scan::new(statements, &[], &[], GroupingStrategy::default())
+    }
+
+    fn active(scan: &Scan) -> Vec<&Statement> {
+        scan.grouped
+            .iter()
+            .filter(|x| x.key != "deleted")
+            .flat_map(|x| x.statements.iter())
+            .collect()
+    }
+
+    #[test]
+    fn merge_halves() {
+        let own = vec![
+            OwnAccount::new(CURRENT, "Assets:Current").unwrap(),
+            OwnAccount::new(SAVINGS, "Assets:Savings").unwrap(),
+        ];
+        let mut scans = vec![
+            scan(vec![statement(2, -10000, CURRENT, SAVINGS)]),
+            scan(vec![
+                statement(3, 10000, SAVINGS, CURRENT),
+                statement(3, 2500, SAVINGS, "BE43068999999501"),
+            ]),
+        ];
+
+        assert_eq!(merge_transfers(&mut scans, &own), 1);
+        let booked = active(&scans[0]);
+        assert_eq!(booked.len(), 1);
+        assert_eq!(booked[0].category.as_deref(), Some("Assets:Savings"));
+        assert!(booked[0].transfer.is_some());
+        assert_eq!(active(&scans[1]).len(), 1);
+        assert_eq!(scans[1].trash().count(), 1);
+
+        // A later transfer of the same amount has no outgoing half left to pair with
+        scans.push(scan(vec![statement(4, 10000, SAVINGS, CURRENT)]));
+        assert_eq!(merge_transfers(&mut scans, &own), 0);
+        assert_eq!(merge_transfers(&mut scans, &own), 0);
+        assert_eq!(active(&scans[2]).len(), 1);
+        assert_eq!(active(&scans[0]).len(), 1);
+
+        // Undoing the merge in both scans lets the halves pair again
+        scans[0].undo();
+        scans[1].undo();
+        assert_eq!(merge_transfers(&mut scans, &own), 1);
+    }
+}
@@ -0,0 +1,58 @@
+use super::{GroupedStatement, Scan, Statement};
+
+impl Scan {
+    /// Deleted statements, they live in the trailing "deleted" group
+    pub fn trash(&self) -> impl Iterator<Item = &Statement> {
+        self.grouped
+            .iter()
+            .filter(|x| x.key == "deleted")
+            .flat_map(|x| x.statements.iter())
+    }
+
+    fn take_deleted(&mut self, keep: impl Fn(&Statement) -> bool) -> Vec<Statement> {
+        let index = match self.group_index("deleted") {
+            Some(x) => x,
+            None => return Vec::new(),
+        };
+        let group = &mut self.grouped[index];
+        let (taken, kept) = group.statements.drain(..).partition(|x| !keep(x));
+        group.statements = kept;
+        taken
+    }
+
+    fn put_back(&mut self, mut statement: Statement, to: &str) {
+        statement.deleted_from = None;
+        statement.transfer = None;
+
+        // A group that is gone comes back with the category its statements had
+        let index = self.group_index(to).unwrap_or_else(|| {
+            let mut group = GroupedStatement::new(to.to_string());
+            group.category = statement.category.clone();
+            self.insert_group(group)
+        });
+        let group = &mut self.grouped[index];
+        group.statements.push(statement);
+        group.inherit_category();
+        group.sort();
+    }
+
+    /// Puts a deleted statement back in the group it came from, or in `to` when given
+    pub fn restore(&mut self, item_id: &str, to: Option<&str>) {
+        for statement in self.take_deleted(|x| x.id.0 != item_id) {
+            let to = to
+                .map(str::trim)
+                .filter(|x| !x.is_empty() && *x != "deleted")
+                .map(String::from)
+                .or_else(|| statement.deleted_from.clone())
+                .unwrap_or_else(|| "restored".to_string());
+            self.put_back(statement, &to);
+        }
+    }
+
+    /// Puts all statements that were deleted from `group_id` back
+    pub fn restore_group(&mut self, group_id: &str) {
+        for statement in self.take_deleted(|x| x.deleted_from.as_deref() != Some(group_id)) {
+            self.put_back(statement, group_id);
+        }
+    }
+}
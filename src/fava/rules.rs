@@ -0,0 +1,154 @@
+use rocket::form::Form;
+use rocket::serde::json::serde_json::json;
+use rocket::{response::Redirect, routes, Build, Rocket, State};
+use rocket_dyn_templates::Template;
+
+use crate::fava::ScanConfigConfig;
+use crate::repository::Repository;
+use crate::util::Error;
+use crate::{context::Context, oauth::AuthUser};
+
+use super::ingest::FavaAccounts;
+use super::models::money;
+use super::models::rules::{Condition, Field, Rule, Rules};
+use super::models::Scans;
+
+fn render(
+    mut context: Context,
+    rules: &[Rule],
+    accounts: &FavaAccounts,
+    errors: &[Error],
+) -> Template {
+    let fields: Vec<_> = Field::ALL.iter().map(|(name, _)| name).collect();
+    context.merge(json!({
+        "rules": rules,
+        "fields": fields,
+        "accounts": accounts.accounts,
+        "errors": errors,
+    }));
+    Template::render("fava/ingest/rules", context.value())
+}
+
+#[get("/")]
+fn get(
+    context: Context,
+    rules: &State<Rules>,
+    accounts: &State<FavaAccounts>,
+    user: AuthUser,
+) -> Result<Template, Redirect> {
+    user.check()?;
+    Ok(rules.with(|rules| render(context, rules, accounts, &[])))
+}
+
+#[derive(FromForm)]
+struct RuleForm<'r> {
+    kind: &'r str,
+    field: &'r str,
+    value: &'r str,
+    min: &'r str,
+    max: &'r str,
+    account: &'r str,
+}
+
+fn parse_bound(bound: &str) -> Result<Option<isize>, Error> {
+    if bound.trim().is_empty() {
+        return Ok(None);
+    }
+    money::parse_cents(bound)
+        .map(Some)
+        .map_err(|e| Error::new("Invalid amount", &e.to_string()))
+}
+
+impl RuleForm<'_> {
+    fn condition(&self) -> Result<Condition, Error> {
+        let field = Field::ALL
+            .iter()
+            .find(|(name, _)| *name == self.field)
+            .map(|(_, field)| *field);
+
+        match (self.kind, field) {
+            ("amount", _) => Ok(Condition::Amount {
+                min: parse_bound(self.min)?,
+                max: parse_bound(self.max)?,
+            }),
+            ("exact", Some(field)) => Ok(Condition::Exact {
+                field,
+                value: self.value.to_string(),
+            }),
+            ("regex", Some(field)) => Ok(Condition::Regex {
+                field,
+                pattern: self.value.to_string(),
+            }),
+            _ => Err(Error::new(
+                "Invalid rule",
+                "Pick a field for exact and regex rules.",
+            )),
+        }
+    }
+}
+
+#[post("/", data = "<user_input>")]
+fn post(
+    context: Context,
+    user_input: Form<RuleForm<'_>>,
+    rules: &State<Rules>,
+    accounts: &State<FavaAccounts>,
+    user: AuthUser,
+) -> Result<Redirect, Template> {
+    if let Err(e) = user.check() {
+        return Ok(e);
+    }
+
+    let rule = user_input.condition().and_then(|condition| {
+        if user_input.account.is_empty() {
+            return Err(Error::new("Invalid rule", "A rule needs an account."));
+        }
+        Rule::new(condition, user_input.account.to_string())
+            .map_err(|e| Error::new("Invalid regex", &e.to_string()))
+    });
+
+    rules.with_save(|rules| match rule {
+        Ok(rule) => {
+            rules.push(rule);
+            Ok(Redirect::to("/fava/ingest/rules"))
+        }
+        Err(e) => Err(render(context, rules, accounts, &[e])),
+    })
+}
+
+#[delete("/<rule_id>")]
+fn delete(rule_id: &str, rules: &State<Rules>, user: AuthUser) -> Redirect {
+    if let Err(e) = user.check() {
+        return e;
+    }
+
+    rules.with_save(|rules| rules.retain(|x| x.id != rule_id));
+    Redirect::to("/fava/ingest/rules")
+}
+
+/// Runs the rules over the uncategorised groups of all pending scans
+#[post("/apply")]
+fn apply(rules: &State<Rules>, scans: &State<Scans>, user: AuthUser) -> Redirect {
+    if let Err(e) = user.check() {
+        return e;
+    }
+
+    rules.with(|rules| {
+        scans.with_save(|scans| {
+            for scan in scans.iter_mut() {
+                scan.edit("Apply the rules", |scan| scan.apply_rules(rules));
+            }
+        })
+    });
+    Redirect::to("/fava/ingest")
+}
+
+pub fn fuel(rocket: Rocket<Build>) -> Rocket<Build> {
+    rocket
+        .mount("/fava/ingest/rules", routes![get, post, delete, apply])
+        .attach(Repository::<Vec<Rule>>::adhoc(
+            "rules config",
+            |c: &ScanConfigConfig| c.rules_location.to_string(),
+            vec![],
+        ))
+}
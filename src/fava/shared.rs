@@ -0,0 +1,44 @@
+use rocket::serde::json::serde_json::json;
+use rocket::{response::Redirect, routes, Build, Rocket, State};
+use rocket_dyn_templates::Template;
+
+use crate::fava::ScanConfigConfig;
+use crate::{context::Context, oauth::AuthUser};
+
+use super::models::{ledger, shared};
+
+/// Who owes what, according to the receivable accounts in the ledger
+#[get("/")]
+fn get(
+    mut context: Context,
+    config: &State<ScanConfigConfig>,
+    user: AuthUser,
+) -> Result<Template, Redirect> {
+    user.check()?;
+
+    let ledger = ledger::read(&config.beancount_location);
+    let balances: Vec<_> = shared::balances(&ledger)
+        .into_iter()
+        .flat_map(|(person, amounts)| {
+            amounts
+                .into_iter()
+                .filter(|(_, cents)| *cents != 0)
+                .map(move |(currency, cents)| {
+                    json!({
+                        "person": person,
+                        "account": shared::receivable(&person),
+                        "currency": currency,
+                        "cents": cents.abs(),
+                        "owes_us": cents > 0,
+                    })
+                })
+        })
+        .collect();
+
+    context.merge(json!({ "balances": balances }));
+    Ok(Template::render("fava/ingest/shared", context.value()))
+}
+
+pub fn fuel(rocket: Rocket<Build>) -> Rocket<Build> {
+    rocket.mount("/fava/ingest/shared", routes![get])
+}
@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use notify::recommended_watcher;
+use notify::EventKind;
+use notify::RecursiveMode;
+use rocket::fairing::AdHoc;
+use rocket::tokio::fs;
+use rocket::tokio::sync::mpsc;
+use rocket::tokio::task;
+use rocket::{Build, Orbit, Rocket};
+
+use crate::fava::ScanConfigConfig;
+
+use super::ingest::push_scan;
+use super::models::aliases::Aliases;
+use super::models::importer;
+use super::models::invoices::Invoices;
+use super::models::own_accounts::OwnAccounts;
+use super::models::rules::Rules;
+use super::models::{Imports, Scans};
+
+/// A file is imported once it didn't change for this long, downloads are written in parts
+const SETTLE: Duration = Duration::from_secs(2);
+
+/// Browsers and sync clients write to these before the real name appears
+const PARTIAL: [&str; 3] = ["part", "crdownload", "tmp"];
+
+#[derive(thiserror::Error, Debug)]
+pub enum WatchError {
+    #[error("IO error. {0}")]
+    IO(#[from] std::io::Error),
+    #[error("Notify error. {0}")]
+    Notify(#[from] notify::Error),
+    #[error("Import task failed. {0}")]
+    Task(#[from] task::JoinError),
+}
+
+/// Watches the import folder and turns statement files into scans, like an upload would.
+///
+/// Files with rows that could not be read become imports to fix by hand,
+/// files that are no statements at all are archived under `failed`.
+pub struct ImportService {
+    base: PathBuf,
+    /// Files seen in the folder and when they last changed
+    pending: HashMap<PathBuf, Instant>,
+    store: Store,
+}
+
+/// The repositories of the server that imported files end up in
+#[derive(Clone)]
+struct Store {
+    archive: PathBuf,
+    scans: Scans,
+    imports: Imports,
+    rules: Rules,
+    aliases: Aliases,
+    invoices: Invoices,
+    own: OwnAccounts,
+    config: ScanConfigConfig,
+}
+
+impl ImportService {
+    /// Only when a folder is configured and the repositories are managed
+    fn from_rocket(rocket: &Rocket<Orbit>) -> Option<Self> {
+        let config = rocket.state::<ScanConfigConfig>()?.clone();
+        let base = PathBuf::from(config.watch_folder.as_ref()?);
+        Some(Self {
+            base,
+            pending: HashMap::new(),
+            store: Store {
+                archive: PathBuf::from(&config.archive_folder),
+                scans: rocket.state::<Scans>()?.clone(),
+                imports: rocket.state::<Imports>()?.clone(),
+                rules: rocket.state::<Rules>()?.clone(),
+                aliases: rocket.state::<Aliases>()?.clone(),
+                invoices: rocket.state::<Invoices>()?.clone(),
+                own: rocket.state::<OwnAccounts>()?.clone(),
+                config,
+            },
+        })
+    }
+
+    /// Hidden and half downloaded files are left alone
+    fn wanted(&self, path: &Path) -> bool {
+        let name = match path.file_name().and_then(|x| x.to_str()) {
+            Some(x) => x,
+            None => return false,
+        };
+        let partial = path
+            .extension()
+            .and_then(|x| x.to_str())
+            .map(|x| PARTIAL.contains(&x))
+            .unwrap_or(false);
+
+        path.parent() == Some(self.base.as_path())
+            && path.is_file()
+            && !name.starts_with('.')
+            && !partial
+    }
+
+    fn mark(&mut self, path: PathBuf) {
+        if self.wanted(&path) {
+            self.pending.insert(path, Instant::now());
+        }
+    }
+
+    fn handle_file_event(&mut self, event: notify::event::Event) {
+        // Backends differ in how precise they tell what changed, the settle time sorts it out
+        match event.kind {
+            EventKind::Modify(_) | EventKind::Create(_) => {
+                for path in event.paths {
+                    self.mark(path);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Imports the files that settled
+    async fn import_settled(&mut self) {
+        let settled: Vec<_> = self
+            .pending
+            .iter()
+            .filter(|(_, changed)| changed.elapsed() >= SETTLE)
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        for path in settled {
+            self.pending.remove(&path);
+            if !path.is_file() {
+                continue;
+            }
+            if let Err(e) = self.import(&path).await {
+                eprintln!("Failed to import {}. {}", path.display(), e);
+            }
+        }
+    }
+
+    async fn import(&self, path: &Path) -> Result<(), WatchError> {
+        let name = path
+            .file_name()
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let buf = fs::read(path).await?;
+        let content = String::from_utf8_lossy(&buf).to_string();
+
+        // Storing takes the locks of the repositories and reads the ledger
+        let store = self.store.clone();
+        let file = name.clone();
+        let folder = task::spawn_blocking(move || store.import(&file, &content)).await?;
+
+        archive(path, &folder, &name).await
+    }
+
+    async fn inner_start(mut self) -> Result<(), WatchError> {
+        use notify::Watcher;
+        let (file_tx, mut file_rx) = mpsc::channel(10);
+        let mut watcher = recommended_watcher(move |x| {
+            let _ = file_tx.blocking_send(x);
+        })?;
+        watcher.watch(&self.base, RecursiveMode::NonRecursive)?;
+
+        // Files dropped while we were not running
+        let mut dir = fs::read_dir(&self.base).await?;
+        while let Ok(Some(entry)) = dir.next_entry().await {
+            self.mark(entry.path());
+        }
+
+        let mut tick = rocket::tokio::time::interval(SETTLE);
+        loop {
+            rocket::tokio::select! {
+                req = file_rx.recv() => {
+                    match req {
+                        Some(Ok(x)) => self.handle_file_event(x),
+                        Some(Err(e)) => eprintln!("{}", e),
+                        None => break,
+                    }
+                },
+                _ = tick.tick() => self.import_settled().await,
+            };
+        }
+
+        Ok(())
+    }
+
+    pub async fn start(self) {
+        if let Err(err) = self.inner_start().await {
+            eprintln!("{}", err);
+        }
+    }
+}
+
+impl Store {
+    /// Makes a scan or an import to fix from the file `name`, returns the folder to archive it in
+    fn import(&self, name: &str, content: &str) -> PathBuf {
+        match importer::read(content, None) {
+            Ok(import) if import.errors.is_empty() => {
+                let id = push_scan(
+                    import.statements,
+                    &self.scans,
+                    &self.rules,
+                    &self.aliases,
+                    &self.invoices,
+                    &self.own,
+                    &self.config,
+                );
+                self.scans.with_save(|scans| {
+                    if let Some(scan) = scans.iter_mut().find(|x| x.id == id) {
+                        scan.source = Some(name.to_string());
+                    }
+                });
+                println!("Imported {} as scan {}", name, id);
+                self.archive.clone()
+            }
+            Ok(import) => {
+                println!(
+                    "Imported {} with {} rows to fix by hand",
+                    name,
+                    import.errors.len()
+                );
+                self.imports.with_save(|imports| imports.push(import));
+                self.archive.clone()
+            }
+            Err(e) => {
+                eprintln!("Could not import {}. {}", name, e);
+                self.archive.join("failed")
+            }
+        }
+    }
+}
+
+/// Moves the file into `folder`, an earlier file with the same name is kept
+async fn archive(path: &Path, folder: &Path, name: &str) -> Result<(), WatchError> {
+    fs::create_dir_all(folder).await?;
+    let mut target = folder.join(name);
+    if target.exists() {
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        target = folder.join(format!("{}-{}", stamp, name));
+    }
+
+    // Renaming fails across file systems, a synced folder often is another one
+    if fs::rename(path, &target).await.is_err() {
+        fs::copy(path, &target).await?;
+        fs::remove_file(path).await?;
+    }
+    Ok(())
+}
+
+pub fn fuel(rocket: Rocket<Build>) -> Rocket<Build> {
+    rocket.attach(AdHoc::on_liftoff("import folder", |rocket| {
+        Box::pin(async move {
+            if let Some(service) = ImportService::from_rocket(rocket) {
+                println!("Watching {} for statements", service.base.display());
+                rocket::tokio::spawn(service.start());
+            }
+        })
+    }))
+}
@@ -76,6 +76,16 @@ handlebars_helper!(image: |name: Json| {
 
 #[rocket::main]
 async fn main() -> Result<(), rocket::Error> {
+    // Any arguments make this a command line run, see `only_scan help`
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        if let Err(e) = fava::cli::run(args).await {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     let statics: Vec<Route> = FileServer::new("static", Options::DotFiles).into();
     let rocket = rocket::build();
 
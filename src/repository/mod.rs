@@ -13,6 +13,16 @@ pub struct Repository<T> {
     location: String,
 }
 
+/// Clones share the same state, services outside of requests use them
+impl<T> Clone for Repository<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            location: self.location.clone(),
+        }
+    }
+}
+
 impl<T> Repository<T> {
     pub fn adhoc<F, C>(name: &'static str, func: F, default: T) -> AdHoc
     where
@@ -49,7 +59,7 @@ impl<T> Repository<T>
 where
     T: for<'de> Deserialize<'de> + Serialize,
 {
-    async fn init_read(location: String, default: T) -> Self {
+    pub async fn init_read(location: String, default: T) -> Self {
         let inner = read_file::<T>(&location).await.unwrap_or(default);
         let out = Self {
             inner: Arc::new(Mutex::new(inner)),